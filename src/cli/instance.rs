@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use clap::Subcommand;
+use tokio::sync::RwLock;
+
+use crate::instance::mc_instance::McInstance;
+use crate::instance::server_import::ImportMode;
+use crate::manager::instance_manager::InstanceManager;
+use crate::mc::curseforge;
+use crate::mc::modpack::Modpack;
+use crate::util::config::load_config;
+use crate::util::http::{DownloadProgress, new_client};
+use crate::util::java::JavaManager;
+
+use super::{halt, run_async};
+
+#[derive(Subcommand)]
+pub(crate) enum InstanceCommand {
+	/// Create instance from `.mrpack` or curseforge modpack zip, restart server to load it
+	Import { name: String, file: PathBuf },
+	/// Take over existing server folder, it's symlinked into instance folder unless `--move` is given
+	ImportServer {
+		name: String,
+		path: PathBuf,
+		#[arg(long = "move")]
+		move_folder: bool,
+	},
+}
+
+/// Print phase of long operation
+struct PhasePrinter;
+
+impl DownloadProgress for PhasePrinter {
+	fn on_progress(&self, _: u64, _: Option<u64>) {}
+
+	fn set_phase(&self, phase: &str) {
+		println!("{phase}");
+	}
+}
+
+impl InstanceCommand {
+	#[inline]
+	pub fn handle(self) {
+		match self {
+			InstanceCommand::Import { name, file } => {
+				Self::import(name, file)
+			}
+			InstanceCommand::ImportServer { name, path, move_folder } => {
+				Self::import_server(name, path, if move_folder { ImportMode::Move } else { ImportMode::Symlink })
+			}
+		}
+	}
+
+	fn import(name: String, file: PathBuf) {
+		run_async(async move {
+			load_config().await;
+			JavaManager::scan().await.unwrap_or_else(|err| halt(err));
+			let mut pack = Modpack::read(&file).await.unwrap_or_else(|err| halt(format!("Failed to read modpack: {err}")));
+			if pack.needs_curseforge() && !curseforge::configured().await {
+				halt("curseforge.api_key is required to import curseforge modpack");
+			}
+			let client = new_client().unwrap_or_else(|err| halt(err));
+			let manual = pack.resolve(&client, &PhasePrinter).await.unwrap_or_else(|err| halt(err));
+			let manager = RwLock::new(InstanceManager::new());
+			if let Err(err) = InstanceManager::import_modpack(&manager, &name, &pack, &file, &PhasePrinter).await {
+				halt(format!("Failed to import modpack: {err:?}"));
+			}
+			println!("Imported {} {} as {name}", pack.name, pack.mc_version);
+			for it in manual {
+				println!("{} has to be downloaded from {} and placed in mods", it.file_name, it.url);
+			}
+		});
+	}
+
+	fn import_server(name: String, path: PathBuf, mode: ImportMode) {
+		run_async(async move {
+			load_config().await;
+			JavaManager::scan().await.unwrap_or_else(|err| halt(err));
+			let instance = McInstance::detect(&path).await.unwrap_or_else(|err| halt(format!("Failed to detect server: {err}")));
+			let mods = instance.scan_mods(false).await.unwrap_or_else(|err| halt(err));
+			println!("Found {} {} with {} mods", instance.mod_type.loader_name(), instance.version(), mods.len());
+			let manager = RwLock::new(InstanceManager::new());
+			if let Err(err) = InstanceManager::import_server(&manager, &name, instance, &path, mode, &PhasePrinter).await {
+				halt(format!("Failed to import server: {err:?}"));
+			}
+			println!("Imported {path:?} as {name}");
+		});
+	}
+}
@@ -0,0 +1,78 @@
+use clap::Subcommand;
+
+use crate::manager::instance_manager::InstanceManager;
+use crate::util::config::load_config;
+use crate::util::java::JavaManager;
+
+use super::{halt, run_async, success};
+
+#[derive(Subcommand)]
+pub(crate) enum JavaCommand {
+	/// Scan and list java runtimes; cli always scans, so `rescan` is the same as `POST /java/rescan`
+	#[command(alias = "rescan")]
+	List,
+	/// Download java runtime using provider from config
+	Install { version: u8 },
+	/// Remove downloaded java runtime by its id
+	Remove { id: String },
+}
+
+impl JavaCommand {
+	#[inline]
+	pub fn handle(self) {
+		match self {
+			JavaCommand::List => {
+				Self::list()
+			}
+			JavaCommand::Install { version } => {
+				Self::install(version)
+			}
+			JavaCommand::Remove { id } => {
+				Self::remove(id)
+			}
+		}
+	}
+
+	fn list() {
+		run_async(async {
+			JavaManager::scan().await.unwrap_or_else(|err| halt(err));
+			for java in JavaManager::versions().await {
+				println!("{}\tjava {}\t{}", java.id(), java.version(), java.path());
+			}
+		});
+	}
+
+	fn install(version: u8) {
+		run_async(async move {
+			load_config().await;
+			match JavaManager::download_version(version).await {
+				Ok(java) => {
+					println!("Installed {} at {}", java.id(), java.path());
+				}
+				Err(err) => {
+					halt(format!("Failed to download java {version}: {err:?}"));
+				}
+			}
+		});
+	}
+
+	fn remove(id: String) {
+		run_async(async move {
+			JavaManager::scan().await.unwrap_or_else(|err| halt(err));
+			let Some(java) = JavaManager::get_by_id(&id).await else {
+				halt(format!("java runtime id={id} doesn't existed!"));
+			};
+			if java.runtime_dir().is_none() {
+				halt(format!("{} is not managed by crate", java.path()));
+			}
+			let users = InstanceManager::new().java_users(&java).await.unwrap_or_else(|err| halt(err));
+			if !users.is_empty() {
+				halt(format!("java runtime is used by {}", users.join(", ")));
+			}
+			match JavaManager::remove(&id).await {
+				Ok(_) => { success() }
+				Err(err) => { halt(format!("{err:?}")) }
+			}
+		});
+	}
+}
@@ -0,0 +1,33 @@
+use clap::Subcommand;
+
+use crate::util::config::load_config;
+use crate::web::{init_manifest_key, ManifestKey};
+
+use super::{halt, run_async};
+
+#[derive(Subcommand)]
+pub(crate) enum KeyCommand {
+	/// Print fingerprint of manifest signing key, for client to pin
+	Fingerprint,
+	/// Print public key of manifest signing key
+	Public,
+}
+
+impl KeyCommand {
+	#[inline]
+	pub fn handle(self) {
+		run_async(async move {
+			load_config().await;
+			init_manifest_key().await.unwrap_or_else(|err| halt(format!("Failed to load manifest signing key: {err}")));
+			let key = ManifestKey::get();
+			match self {
+				KeyCommand::Fingerprint => {
+					println!("{}", key.fingerprint);
+				}
+				KeyCommand::Public => {
+					print!("{}", key.key);
+				}
+			}
+		});
+	}
+}
@@ -7,11 +7,17 @@ use std::process::exit;
 use clap::{Parser, Subcommand};
 use sqlx::{Pool, Sqlite};
 
+use instance::InstanceCommand;
+use java::JavaCommand;
+use key::KeyCommand;
 use user::UserCommand;
 
 use crate::db;
 use crate::db::DbWrapper;
 
+mod instance;
+mod java;
+mod key;
 mod user;
 
 pub fn intercept() {
@@ -91,6 +97,21 @@ enum Commands {
 		#[command(subcommand)]
 		cmd: UserCommand
 	},
+	/// Manage minecraft instance
+	Instance {
+		#[command(subcommand)]
+		cmd: InstanceCommand
+	},
+	/// Manage java runtime
+	Java {
+		#[command(subcommand)]
+		cmd: JavaCommand
+	},
+	/// Manifest signing key
+	Key {
+		#[command(subcommand)]
+		cmd: KeyCommand
+	},
 }
 
 impl Commands {
@@ -100,6 +121,15 @@ impl Commands {
 			Commands::User { cmd } => {
 				cmd.handle();
 			}
+			Commands::Instance { cmd } => {
+				cmd.handle();
+			}
+			Commands::Java { cmd } => {
+				cmd.handle();
+			}
+			Commands::Key { cmd } => {
+				cmd.handle();
+			}
 		}
 	}
 }
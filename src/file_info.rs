@@ -1,23 +1,53 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
 use sha2::Sha256;
-use tokio::fs::File;
+use tokio::fs::{File, metadata};
 use tokio::io::AsyncReadExt;
+use tokio::sync::RwLock;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+use crate::mc::mc_config::relative_name;
+
+/// Hash of file by its path, reused while size and modified time are unchanged
+static HASH_CACHE: RwLock<BTreeMap<PathBuf, (u64, SystemTime, String)>> = RwLock::const_new(BTreeMap::new());
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct FileInfo {
+	/// Path relative to instance folder, separated by `/`
 	pub file: String,
+	pub size: u64,
+	/// Sha256 of file
 	pub hash: String,
 }
 
 impl FileInfo {
-	pub async fn new(path: PathBuf) -> Result<Self> {
-		let hash = hash_file(File::open(&path).await?).await?;
+	/// parent: folder that `file` is relative to
+	pub async fn new(parent: &Path, path: PathBuf) -> Result<Self> {
+		Self::named(relative_name(parent, &path), path).await
+	}
+
+	/// file: name client sees, it doesn't have to match `path` (eg. files from client folder)
+	pub async fn named(file: String, path: PathBuf) -> Result<Self> {
+		let meta = metadata(&path).await?;
+		let modified = meta.modified()?;
+		let cached = HASH_CACHE.read().await.get(&path)
+			.filter(|(size, time, _)| *size == meta.len() && *time == modified)
+			.map(|(_, _, hash)| hash.clone());
+		let hash = match cached {
+			Some(it) => { it }
+			None => {
+				let hash = hash_file(File::open(&path).await?).await?;
+				HASH_CACHE.write().await.insert(path.clone(), (meta.len(), modified, hash.clone()));
+				hash
+			}
+		};
 		Ok(Self {
-			file: path.file_name().unwrap().to_string_lossy().to_string(),
+			file,
+			size: meta.len(),
 			hash,
 		})
 	}
@@ -47,4 +77,4 @@ pub async fn hash_file(mut file: File) -> Result<String> {
 		};
 	}
 	Ok(context.finalize()[..].iter().map(|x| format!("{:02x}", x)).collect::<String>())
-}
\ No newline at end of file
+}
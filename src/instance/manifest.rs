@@ -0,0 +1,228 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs::{read, remove_file};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::file_info::FileInfo;
+use crate::instance::mc_instance::{McInstance, ModType};
+use crate::util::fs::write_file;
+
+/// Revisions kept per instance for delta sync
+static MAX_REVISIONS: usize = 32;
+/// Guard revision index from concurrent request, it also keeps the last recorded revision of each history folder
+/// so unchanged manifest doesn't touch the disk
+static HISTORY_LOCK: Mutex<BTreeMap<PathBuf, String>> = Mutex::const_new(BTreeMap::new());
+
+/// Everything client needs to download to be in sync with instance
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Manifest {
+	/// Hash of manifest content, it only changes when any file or version is changed
+	pub revision: String,
+	pub minecraft: String,
+	/// Loader name, see [ModType::loader_name]
+	pub loader: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub loader_version: Option<String>,
+	/// Sorted by path
+	pub files: Vec<FileInfo>,
+}
+
+impl Manifest {
+	/// List every file in `dist_folder` of instance, see [crate::mc::mc_config::MinecraftConfig::dist_files]
+	pub async fn build(instance: &McInstance) -> Result<Self> {
+		let paths = instance.config.dist_files().await?;
+		let mut files = Vec::with_capacity(paths.len());
+		// sorted by BTreeMap
+		for (name, path) in paths {
+			files.push(FileInfo::named(name, path).await?);
+		}
+
+		let loader_version = match &instance.mod_type {
+			ModType::Forge(ver) | ModType::NeoForge(ver) => { Some(ver.clone()) }
+			_ => { None }
+		};
+		let mut manifest = Self {
+			revision: String::new(),
+			minecraft: instance.version().to_string(),
+			loader: instance.mod_type.loader_name().to_string(),
+			loader_version,
+			files,
+		};
+		manifest.revision = manifest.compute_revision();
+		Ok(manifest)
+	}
+
+	fn compute_revision(&self) -> String {
+		let mut sha = Sha256::default();
+		sha.update(format!("{}\n{}\n{}\n", self.minecraft, self.loader, self.loader_version.as_deref().unwrap_or_default()));
+		for file in &self.files {
+			sha.update(format!("{}\0{}\0{}\n", file.file, file.size, file.hash));
+		}
+		// short hash is enough to tell revisions of one instance apart
+		sha.finalize()[..8].iter().map(|it| format!("{:02x}", it)).collect()
+	}
+
+	/// Quoted revision
+	pub fn etag(&self) -> String {
+		format!("\"{}\"", self.revision)
+	}
+}
+
+/// Changes between two manifest revisions
+#[derive(Serialize, Debug, Clone)]
+pub struct ManifestDiff {
+	pub from: String,
+	pub to: String,
+	pub minecraft: String,
+	pub loader: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub loader_version: Option<String>,
+	pub added: Vec<FileInfo>,
+	/// Paths of removed files
+	pub removed: Vec<String>,
+	/// Files with different content at the same path
+	pub changed: Vec<FileInfo>,
+}
+
+/// Response of delta sync, full manifest is sent if base revision is unknown
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ManifestDelta {
+	Diff(ManifestDiff),
+	Full(Manifest),
+}
+
+impl Manifest {
+	/// What has to be done to turn `base` into this manifest, same idea as [crate::instance::mc_mod::MinecraftMod::diff_mod]
+	pub fn diff(&self, base: &Manifest) -> ManifestDiff {
+		let mut existing: HashMap<&str, &FileInfo> = base.files.iter().map(|it| (it.file.as_str(), it)).collect();
+		let mut added = vec![];
+		let mut changed = vec![];
+		for file in &self.files {
+			match existing.remove(file.file.as_str()) {
+				Some(old) if old.hash == file.hash => {}
+				Some(_) => { changed.push(file.clone()) }
+				None => { added.push(file.clone()) }
+			}
+		}
+		let mut removed: Vec<String> = existing.into_keys().map(|it| it.to_string()).collect();
+		removed.sort();
+		ManifestDiff {
+			from: base.revision.clone(),
+			to: self.revision.clone(),
+			minecraft: self.minecraft.clone(),
+			loader: self.loader.clone(),
+			loader_version: self.loader_version.clone(),
+			added,
+			removed,
+			changed,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct RevisionEntry {
+	revision: String,
+	/// Unix time in seconds when revision is first seen
+	time: u64,
+}
+
+/// Recent manifests of instance stored in `.manifest_history/<revision>.json`, `index.json` list them oldest first
+pub struct ManifestHistory {
+	folder: PathBuf,
+}
+
+impl ManifestHistory {
+	pub fn new(instance: &McInstance) -> Result<Self> {
+		Ok(Self { folder: instance.dir(".manifest_history")? })
+	}
+
+	async fn index(&self) -> Vec<RevisionEntry> {
+		match read(self.folder.join("index.json")).await {
+			Ok(data) => { serde_json::from_slice(&data).unwrap_or_default() }
+			Err(_) => { vec![] }
+		}
+	}
+
+	/// Remember manifest if its revision is new, the oldest revisions are dropped
+	pub async fn record(&self, manifest: &Manifest) -> Result<()> {
+		let mut recorded = HISTORY_LOCK.lock().await;
+		if recorded.get(&self.folder) == Some(&manifest.revision) {
+			return Ok(());
+		}
+		let mut index = self.index().await;
+		if index.last().is_some_and(|it| it.revision == manifest.revision) {
+			recorded.insert(self.folder.clone(), manifest.revision.clone());
+			return Ok(());
+		}
+		debug!("recording manifest revision {}", manifest.revision);
+		index.retain(|it| it.revision != manifest.revision);
+		write_file(self.folder.join(format!("{}.json", manifest.revision)), serde_json::to_vec(manifest)?).await?;
+		index.push(RevisionEntry {
+			revision: manifest.revision.clone(),
+			time: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+		});
+		while index.len() > MAX_REVISIONS {
+			let old = index.remove(0);
+			remove_file(self.folder.join(format!("{}.json", old.revision))).await.ok();
+		}
+		write_file(self.folder.join("index.json"), serde_json::to_vec_pretty(&index)?).await?;
+		recorded.insert(self.folder.clone(), manifest.revision.clone());
+		Ok(())
+	}
+
+	/// None if revision is expired or never existed
+	pub async fn get(&self, revision: &str) -> Option<Manifest> {
+		// revision is used as file name
+		if !revision.chars().all(|it| it.is_ascii_hexdigit()) {
+			return None;
+		}
+		let data = read(self.folder.join(format!("{revision}.json"))).await.ok()?;
+		serde_json::from_slice(&data).ok()
+	}
+
+	/// Diff from base revision, or full manifest if base is unknown
+	pub async fn delta(&self, current: Manifest, from: &str) -> ManifestDelta {
+		match self.get(from).await {
+			Some(base) => { ManifestDelta::Diff(current.diff(&base)) }
+			None => { ManifestDelta::Full(current) }
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::file_info::FileInfo;
+	use crate::instance::manifest::Manifest;
+
+	fn manifest(files: &[(&str, &str)]) -> Manifest {
+		let mut manifest = Manifest {
+			revision: String::new(),
+			minecraft: "1.20.1".to_string(),
+			loader: "forge".to_string(),
+			loader_version: Some("47.2.0".to_string()),
+			files: files.iter().map(|(file, hash)| FileInfo { file: file.to_string(), size: 1, hash: hash.to_string() }).collect(),
+		};
+		manifest.revision = manifest.compute_revision();
+		manifest
+	}
+
+	#[test]
+	fn test_diff() {
+		let base = manifest(&[("mods/a.jar", "1"), ("mods/b.jar", "2"), ("mods/c.jar", "3")]);
+		let current = manifest(&[("mods/a.jar", "1"), ("mods/b.jar", "4"), ("mods/d.jar", "5")]);
+		assert_ne!(base.revision, current.revision);
+		let diff = current.diff(&base);
+		assert_eq!(diff.added.iter().map(|it| it.file.as_str()).collect::<Vec<_>>(), vec!["mods/d.jar"]);
+		assert_eq!(diff.changed.iter().map(|it| it.file.as_str()).collect::<Vec<_>>(), vec!["mods/b.jar"]);
+		assert_eq!(diff.removed, vec!["mods/c.jar"]);
+		assert!(current.diff(&current).added.is_empty());
+	}
+}
@@ -1,13 +1,14 @@
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::io;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use anyhow::{anyhow, Result};
-use axum::{extract, Json};
-use axum::body::{Body, BoxBody, boxed};
-use axum::http::{Request, Response, StatusCode, Uri};
+use anyhow::{anyhow, bail, Result};
+use axum::extract;
+use axum::extract::multipart::MultipartError;
+use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
@@ -18,20 +19,34 @@ use tokio::fs::{create_dir_all, File, metadata, read_dir, remove_dir_all, remove
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::spawn;
 use tokio::task::JoinHandle;
-use tower::ServiceExt;
-use tower_http::services::ServeDir;
+use tracing::{debug, error, warn};
 
-use crate::instance::mc_mod::MinecraftMod;
-use crate::instance::mc_server::MinecraftServer;
+use crate::file_info::FileInfo;
+use crate::instance::mc_mod::{DISABLED_SUFFIX, InstalledMod, MinecraftMod};
+use crate::instance::mc_server::{ConsoleProfile, MinecraftServer};
+use crate::instance::mod_history::ModHistory;
+use crate::instance::mod_validator;
+use crate::instance::mod_validator::{ModIssue, Severity};
+use crate::instance::server_import;
 use crate::instance::mc_server::MinecraftServerStatus::STOPPED;
-use crate::mc::mc_config::MinecraftConfig;
-use crate::mc::mc_version::java_for;
-use crate::util::errors::reqwest_to_io;
-use crate::util::fs::{create_if_not_existed, OwnedDirEntry};
-use crate::util::http::{download_to, new_client};
-use crate::util::java::JavaManager;
+use crate::mc::forge;
+use crate::mc::forge::ForgeArtifact;
+use crate::mc::mc_config::{MinecraftConfig, relative_name};
+use crate::mc::mc_version::{java_requirement, JavaVersionForMc, PROXY};
+use crate::mc::{mojang, papermc, version_cache};
+use crate::util::artifact_cache;
+use crate::util::config::get_config;
+use crate::util::errors;
+use crate::util::errors::{ErrorWrapper, reqwest_to_io};
+use crate::util::fs::{create_if_not_existed, OwnedDirEntry, sha256};
+use crate::util::http::{DownloadProgress, FileHash, new_client};
+use crate::util::java::{JavaInfo, JavaManager};
 
 static CONFIG_DOCS: &str = include_str!("../resources/config_docs.yml");
+/// Instance config in instance folder
+static INSTANCE_CONFIG: &str = "config.yml";
+/// Instance config of imported server folder, server may have its own `config.yml` (bungeecord, waterfall)
+pub(crate) static ADOPTED_CONFIG: &str = ".mmcupdater.yml";
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct McInstance {
@@ -53,6 +68,9 @@ pub struct McInstance {
 	/// List of mods
 	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	pub mods: Vec<MinecraftMod>,
+	/// Imported from existing server folder, config is saved as [ADOPTED_CONFIG]
+	#[serde(skip)]
+	adopted: bool,
 }
 
 impl Default for McInstance {
@@ -64,6 +82,7 @@ impl Default for McInstance {
 			mod_type: Default::default(),
 			mods: vec![],
 			_server_instance: None,
+			adopted: false,
 		}
 	}
 }
@@ -71,11 +90,24 @@ impl Default for McInstance {
 impl McInstance {
 	pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
 		let path = path.as_ref();
-		let config = path.join("config.yml");
-		let mut cfg_file = match File::open(&config).await {
-			Ok(f) => { f }
+		match Self::read_config(path).await? {
+			Some(config) => { Ok(config) }
+			None => { Self::generate(path, "", ModType::default(), &()).await }
+		}
+	}
+
+	/// Read instance from its `config.yml` (or [ADOPTED_CONFIG]) without initializing it; None if config is missing or invalid
+	pub async fn read_config(path: impl AsRef<Path>) -> Result<Option<Self>> {
+		let path = path.as_ref();
+		let (mut cfg_file, adopted) = match File::open(path.join(ADOPTED_CONFIG)).await {
+			Ok(f) => { (f, true) }
 			Err(_) => {
-				return Self::generate(path, "", ModType::default()).await;
+				match File::open(path.join(INSTANCE_CONFIG)).await {
+					Ok(f) => { (f, false) }
+					Err(_) => {
+						return Ok(None);
+					}
+				}
 			}
 		};
 		let mut data = Vec::new();
@@ -86,10 +118,11 @@ impl McInstance {
 			if config.name.is_empty() {
 				config.name = path.file_name().map(|it| it.to_string_lossy().to_string()).expect("Instance name");
 			}
+			config.adopted = adopted;
 			Arc::get_mut(&mut config.config).unwrap().directory = path.canonicalize()?.to_string_lossy().to_string();
-			Ok(config)
+			Ok(Some(config))
 		} else {
-			Self::generate(path, "", ModType::default()).await
+			Ok(None)
 		}
 	}
 
@@ -99,7 +132,8 @@ impl McInstance {
 	/// # Arguments 
 	///
 	/// * `folder`: Instance folder (will replace existing)
-	pub async fn generate(folder: &Path, version: &str, mod_type: ModType) -> Result<McInstance> {
+	/// * `progress`: Receive progress of java download
+	pub async fn generate(folder: &Path, version: &str, mod_type: ModType, progress: &dyn DownloadProgress) -> Result<McInstance> {
 		let mut cfg = Self::default();
 		#[allow(clippy::field_reassign_with_default)]
 		{ cfg.mod_type = mod_type; }
@@ -110,7 +144,7 @@ impl McInstance {
 		if cfg.version.is_empty() {
 			match &mut cfg.mod_type {
 				ModType::Vanilla | ModType::Purpur => {
-					let versions = ModType::Purpur.versions(&new_client()?).await?;
+					let versions = version_cache::versions(&ModType::Purpur).await?;
 					match versions.latest() {
 						None => {}
 						Some(ver) => {
@@ -118,23 +152,29 @@ impl McInstance {
 						}
 					}
 				}
+				n @ (ModType::Paper | ModType::Folia | ModType::Velocity | ModType::Waterfall) => {
+					let versions = version_cache::versions(n).await?;
+					if let Some(ver) = versions.latest() {
+						cfg.version = ver.to_string();
+					}
+				}
 				n => {
 					// use to generate jump instruction
 					#[allow(clippy::never_loop)]
 					loop {
-						if let ModType::Forge(ver) = n {
+						if let ModType::Forge(ver) | ModType::NeoForge(ver) = n {
 							if !ver.is_empty() {
 								// jump out
 								break;
 							}
 						}
-						let versions = n.versions(&new_client()?).await?;
+						let versions = version_cache::versions(n).await?;
 						match versions.latest() {
 							None => {}
 							Some(ver) => {
 								cfg.version = ver.to_string();
 								if let Some(it) = versions.latest_for(ver) {
-									if let ModType::Forge(fver) = n {
+									if let ModType::Forge(fver) | ModType::NeoForge(fver) = n {
 										*fver = it.to_string();
 									}
 								}
@@ -146,44 +186,124 @@ impl McInstance {
 			}
 		}
 		{
-			let java = java_for(&cfg.version).unwrap();
 			let folder = folder.to_string_lossy().to_string();
 			let mut c = MinecraftConfig::clone(&cfg.config);
 			c.directory = folder;
-			if c.java.is_empty() {
-				let j = JavaManager::get_version(java.recommended).await;
-				match j {
-					None => {
-						c.java = "java".to_string();
-					}
-					Some(it) => {
-						c.java = it.path_for(&c.directory).to_string_lossy().to_string();
-						let extra_args = it.performance_args();
-						if !extra_args.is_empty()
-							&& !c.jvm_args.iter().any(|it| it == extra_args[0]) {
-							c.jvm_args.extend(extra_args.iter().map(|it| it.to_string()));
-						}
-					}
-				}
+			if cfg.mod_type.is_proxy() {
+				// proxy will treat `nogui` as unknown option
+				c.args.clear();
 			}
 			cfg.config = Arc::new(c);
 		}
+		cfg.choose_java(progress).await?;
 		cfg.save().await?;
 		Ok(cfg)
 	}
 
+	/// Instance of existing server folder, loader and version are detected from server jar
+	/// and launch arguments are taken from its start script. Nothing is written, see [Self::adopt]
+	pub async fn detect(folder: &Path) -> Result<McInstance> {
+		if metadata(folder.join(ADOPTED_CONFIG)).await.is_ok() {
+			bail!("{folder:?} is already imported");
+		}
+		let script = server_import::read_script(folder).await?;
+		let loader = server_import::detect_loader(folder, script.as_ref()).await?;
+		let mut cfg = Self {
+			name: folder.file_name().map(|it| it.to_string_lossy().to_string()).expect("Instance name"),
+			version: loader.version,
+			mod_type: loader.mod_type,
+			..Self::default()
+		};
+		let mut c = MinecraftConfig::clone(&cfg.config);
+		c.directory = folder.canonicalize()?.to_string_lossy().to_string();
+		if cfg.mod_type.is_proxy() {
+			c.args.clear();
+		}
+		loader.launch.apply(&mut c);
+		if let Some(script) = script {
+			script.apply(&mut c);
+		}
+		cfg.config = Arc::new(c);
+		Ok(cfg)
+	}
+
+	/// Take over detected instance (see [Self::detect]) which is now at `folder` and save its config as [ADOPTED_CONFIG].
+	/// Java runtime is picked unless start script use specific one
+	pub async fn adopt(&mut self, folder: &Path, progress: &dyn DownloadProgress) -> Result<()> {
+		self.adopted = true;
+		let mut c = MinecraftConfig::clone(&self.config);
+		// resolve symlink like `read_config`, relative java path should be the same after restart
+		c.directory = folder.canonicalize()?.to_string_lossy().to_string();
+		self.config = Arc::new(c);
+		self.choose_java(progress).await?;
+		self.save().await
+	}
+
+	/// Use recommended java runtime (it may be downloaded) if java isn't configured
+	async fn choose_java(&mut self, progress: &dyn DownloadProgress) -> Result<()> {
+		let java = self.java_requirement().await
+			.ok_or_else(|| anyhow!("Unknown minecraft version {:?}", self.version))?;
+		if !self.config.java.is_empty() {
+			return Ok(());
+		}
+		let mut c = MinecraftConfig::clone(&self.config);
+		match JavaManager::get_version(java.recommended, progress).await {
+			None => {
+				c.java = "java".to_string();
+			}
+			Some(it) => {
+				c.use_java(&it);
+			}
+		}
+		self.config = Arc::new(c);
+		Ok(())
+	}
+
 	// try to initialize instance (download server file as needed)
-	pub async fn init(&mut self) -> Result<()> {
-		let cfg = &self.config;
+	pub async fn init(&mut self, progress: &dyn DownloadProgress) -> Result<()> {
 		// server file is not existed
-		let server_path = self.dir(&cfg.server_file)?;
-		if metadata(&server_path).await.is_err() {
+		if !self.server_installed().await {
 			// it will fail to init if failed to download
-			self.mod_type.download_server(&new_client()?, &self.version, server_path).await?;
+			self.install_server(progress).await?;
 		};
-		create_if_not_existed(self.dir("eula.txt")?, b"eula=true").await?;
+		if !self.mod_type.is_proxy() {
+			create_if_not_existed(self.dir("eula.txt")?, b"eula=true").await?;
+		}
 		if self._server_instance.is_none() {
-			self._server_instance = Some(Arc::new(MinecraftServer::new(self.name.clone(), None)?));
+			self._server_instance = Some(Arc::new(MinecraftServer::new(self.name.clone(), self.mod_type.console_profile(), None)?));
+		}
+		Ok(())
+	}
+
+	/// Check if file used to launch server is existed
+	async fn server_installed(&self) -> bool {
+		let cfg = &self.config;
+		let file = match cfg.launch_args.first().and_then(|it| it.strip_prefix('@')) {
+			Some(args_file) => { args_file }
+			None => { cfg.server_file.as_str() }
+		};
+		match self.dir(file) {
+			Ok(path) => { metadata(path).await.is_ok() }
+			Err(_) => { false }
+		}
+	}
+
+	/// Download server file, forge-like loader will run its installer and update launch arguments
+	async fn install_server(&mut self, progress: &dyn DownloadProgress) -> Result<()> {
+		let client = new_client()?;
+		match self.mod_type.forge_artifact(&self.version) {
+			Some(artifact) => {
+				let launch = forge::install(&client, &artifact, &self.config, progress).await?;
+				let mut c = MinecraftConfig::clone(&self.config);
+				launch.apply(&mut c);
+				self.config = Arc::new(c);
+				self.save().await?;
+			}
+			None => {
+				let server_path = self.dir(&self.config.server_file)?;
+				progress.set_phase(&format!("downloading {} {}", self.mod_type.loader_name(), self.version));
+				self.mod_type.download_server(&client, &self.version, server_path, progress).await?;
+			}
 		}
 		Ok(())
 	}
@@ -227,24 +347,49 @@ impl McInstance {
 		}
 	}
 
-	async fn get_web_file(&self, uri: Uri) -> Result<Response<BoxBody>, (StatusCode, String)> {
-		let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
-		match ServeDir::new(&self.config.directory).oneshot(req).await {
-			Ok(res) => Ok(res.map(boxed)),
-			Err(err) => Err(
-				(
-					StatusCode::INTERNAL_SERVER_ERROR,
-					format!("Something went wrong: {err}")
-				)
-			),
+	/// Distributed file by name client sees (see [MinecraftConfig::dist_files]) and where it's stored,
+	/// None if it's not distributed
+	pub async fn dist_file(&self, name: &str) -> Result<Option<(FileInfo, PathBuf)>> {
+		let parent = self.dir("")?;
+		// normalized inside instance, `..` can't escape it
+		let Ok(file) = self.dir(name.trim_start_matches('/')) else {
+			return Ok(None);
+		};
+		let name = relative_name(&parent, &file);
+		let client = self.config.client_dir()?;
+		let mut allowed = false;
+		for folder in &self.config.dist_folder {
+			allowed |= file.starts_with(self.dir(folder)?);
+		}
+		if !allowed || file.starts_with(&client) || !self.config.is_distributed(&name, self.config.exclude_regex()?.as_ref()) {
+			return Ok(None);
 		}
+		// client folder replaces server file
+		for path in [client.join(&name), file] {
+			if metadata(&path).await.is_ok_and(|it| it.is_file()) {
+				return Ok(Some((FileInfo::named(name, path.clone()).await?, path)));
+			}
+		}
+		Ok(None)
+	}
+
+	/// Java requirement of this instance; None if minecraft version is unknown
+	pub async fn java_requirement(&self) -> Option<JavaVersionForMc> {
+		self.mod_type.java_requirement(&self.version).await
 	}
 
+	/// Switch instance to java runtime and save config, caller should check [Self::java_requirement]
+	pub async fn use_java(&mut self, java: &JavaInfo) -> Result<()> {
+		let mut c = MinecraftConfig::clone(&self.config);
+		c.use_java(java);
+		self.config = Arc::new(c);
+		self.save().await
+	}
 
 	/// Serialize configuration to file, config file will stored in instance folder (self.config.directory)
 	pub async fn save(&self) -> Result<()> {
 		let data = serde_yaml::to_string(self).expect("Serialize config");
-		let mut cfg_file = File::create((AsRef::<Path>::as_ref(&self.config.directory)).join("config.yml")).await?;
+		let mut cfg_file = File::create(self.config_file()).await?;
 		cfg_file.write_all(data.as_bytes()).await?;
 		cfg_file.write_all(CONFIG_DOCS.as_bytes()).await?;
 		cfg_file.flush().await?;
@@ -252,60 +397,250 @@ impl McInstance {
 		Ok(())
 	}
 
-	fn update_config(&mut self) {}
-
-	pub async fn update(&mut self, mut multipart: extract::Multipart) -> impl IntoResponse {
-		while let Ok(Some(field)) = multipart.next_field().await {
-			let name = field.name().unwrap();
-			if let "file" = name {
-				let filename = field.file_name()
-					.and_then(|it| it.rsplit('/').next())
-					.map(|it| it.to_string());
-				if let Some(true) = filename.as_ref().map(|it| it.ends_with(".jar")) {
-					let filename = filename.unwrap();
-
-					let dl_folder = Path::new("download");
-					if !dl_folder.exists() {
-						create_dir_all(&dl_folder).await.unwrap();
-					}
-					let tmp = dl_folder.join(filename.as_str());
+	/// Path of instance config file
+	pub fn config_file(&self) -> PathBuf {
+		let name = if self.adopted { ADOPTED_CONFIG } else { INSTANCE_CONFIG };
+		AsRef::<Path>::as_ref(&self.config.directory).join(name)
+	}
 
-					let mut tmp_file = File::create(&tmp).await.unwrap();
+	/// Save uploaded jars (multipart field `file`, can be repeated) into `mods`; mod with the same id is replaced.
+	/// Server isn't restarted, see [Self::restart_if_running]
+	pub async fn update(&self, mut multipart: extract::Multipart) -> errors::Result<Vec<MinecraftMod>> {
+		let mod_dir = self.mod_dir()?;
+		create_dir_all(&mod_dir).await?;
+		// same filesystem as `mods` so it can be renamed
+		let dl_folder = self.dir(".upload")?;
+		create_dir_all(&dl_folder).await?;
+		let mut installed = self.scan_mods(false).await?;
+		let mut uploaded = Vec::new();
+		while let Some(mut field) = multipart.next_field().await.map_err(bad_upload)? {
+			if field.name() != Some("file") {
+				continue;
+			}
+			let Some(filename) = field.file_name().and_then(|it| it.rsplit(['/', '\\']).next()).map(|it| it.to_string()) else {
+				continue;
+			};
+			if !is_mod_file_name(&filename) {
+				return Err(ErrorWrapper::custom(StatusCode::BAD_REQUEST, "Only .jar file can be uploaded"));
+			}
+			// concurrent upload of the same name must not share the file
+			let tmp = dl_folder.join(format!("{:016x}-{filename}.part", rand::random::<u64>()));
+			let received = async {
+				let mut tmp_file = File::create(&tmp).await?;
+				while let Some(data) = field.chunk().await.map_err(bad_upload)? {
+					tmp_file.write_all(&data).await?;
+				}
+				tmp_file.flush().await?;
+				Ok::<_, ErrorWrapper>(())
+			}.await;
+			if let Err(err) = received {
+				remove_file(&tmp).await.ok();
+				return Err(err);
+			}
 
-					let mut reader = field;
-					while let Some(Ok(data)) = reader.next().await {
-						tmp_file.write_all(&data).await.ok();
-					};
-					tmp_file.flush().await.unwrap();
+			let mod_info = match MinecraftMod::try_parse(&tmp).await {
+				Ok(it) => { it }
+				Err(err) => {
+					warn!("Uploaded {filename} is not a valid jar: {err}");
+					remove_file(&tmp).await.ok();
+					return Err(ErrorWrapper::custom(StatusCode::BAD_REQUEST, "Uploaded file is not a valid jar"));
+				}
+			};
+			// replace older version of the same mod
+			let mut replaced = vec![];
+			installed.retain(|old| {
+				let same = if mod_info.id.is_empty() { old.info.name == mod_info.name } else { old.info.id == mod_info.id };
+				if same && old.info.file_name != filename {
+					replaced.push(if old.enabled { old.info.file_name.clone() } else { format!("{}{DISABLED_SUFFIX}", old.info.file_name) });
+				}
+				!same
+			});
+			for file in replaced {
+				debug!("Replacing {file} with {filename}");
+				remove_file(mod_dir.join(file)).await.ok();
+			}
+			remove_file(mod_dir.join(format!("{filename}{DISABLED_SUFFIX}"))).await.ok();
+			rename(&tmp, mod_dir.join(&filename)).await?;
+			uploaded.push(mod_info);
+		}
+		Ok(uploaded)
+	}
 
-					let mod_info = MinecraftMod::try_parse(&tmp).await.unwrap();
+	/// Mods in [ModType::mod_folder] including disabled one, [MinecraftMod::file_name] is always name of enabled jar
+	pub async fn list_mods(&self) -> Result<Vec<InstalledMod>> {
+		self.scan_mods(true).await
+	}
 
-					let old = self.mods.iter().find(|it| it.name == mod_info.name);
-					let mod_dir = self.dir("mods").unwrap();
-					if let Some(mcmod) = old {
-						let old_file = mod_dir.join(mcmod.file_name.as_str());
-						remove_file(&old_file).await.ok();
+	/// Skip hashing if `hash` is false, hash will be empty
+	pub(crate) async fn scan_mods(&self, hash: bool) -> Result<Vec<InstalledMod>> {
+		let mod_dir = self.mod_dir()?;
+		if !mod_dir.exists() {
+			return Ok(Vec::new());
+		}
+		let files = crate::file_scanner::scan_files(mod_dir, |it| {
+			let name = it.file_name().to_string_lossy().to_string();
+			is_mod_file_name(name.strip_suffix(DISABLED_SUFFIX).unwrap_or(&name))
+		}).await?;
+		let mods = FuturesUnordered::new();
+		for file in files {
+			mods.push(async move {
+				// broken jar is still listed so it can be removed
+				let (mut info, error) = match MinecraftMod::try_parse(&file).await {
+					Ok(it) => { (it, None) }
+					Err(err) => {
+						let file_name = file.file_name().map(|it| it.to_string_lossy()).unwrap_or_default();
+						(MinecraftMod::unknown(&file_name), Some(err.to_string()))
+					}
+				};
+				let enabled = match info.file_name.strip_suffix(DISABLED_SUFFIX) {
+					Some(name) => {
+						info.file_name = name.to_string();
+						false
 					}
+					None => { true }
+				};
+				info.source = self.mods.iter()
+					.find(|it| it.file_name == info.file_name)
+					.and_then(|it| it.source.clone());
+				Ok::<_, anyhow::Error>(InstalledMod {
+					info,
+					enabled,
+					sha256: if hash { sha256(&file).await? } else { String::new() },
+					size: metadata(&file).await?.len(),
+					error,
+				})
+			});
+		}
+		let mut mods: Vec<InstalledMod> = mods
+			.collect::<Vec<_>>()
+			.await
+			.into_iter()
+			.filter_map(|it| it.ok())
+			.collect();
+		mods.sort_by(|a, b| a.info.file_name.cmp(&b.info.file_name));
+		Ok(mods)
+	}
 
-					rename(tmp, mod_dir.join(filename.as_str())).await.ok();
+	/// Find mod file by name of enabled jar, return path and whether it's enabled
+	pub async fn find_mod(&self, file_name: &str) -> Result<Option<(PathBuf, bool)>> {
+		if !is_mod_file_name(file_name) {
+			return Ok(None);
+		}
+		let mod_dir = self.mod_dir()?;
+		let enabled = mod_dir.join(file_name);
+		if metadata(&enabled).await.is_ok() {
+			return Ok(Some((enabled, true)));
+		}
+		let disabled = mod_dir.join(format!("{file_name}{DISABLED_SUFFIX}"));
+		if metadata(&disabled).await.is_ok() {
+			return Ok(Some((disabled, false)));
+		}
+		Ok(None)
+	}
 
-					// don't restart if server is gracefully stopped
-					if let Some(mc_server) = self._server_instance.as_ref() {
-						let server_status = mc_server.status().await;
+	/// return bool: true if mod is found
+	pub async fn remove_mod(&mut self, file_name: &str) -> Result<bool> {
+		match self.find_mod(file_name).await? {
+			Some((path, _)) => {
+				remove_file(path).await?;
+				if self.mods.iter().any(|it| it.file_name == file_name) {
+					self.mods.retain(|it| it.file_name != file_name);
+					self.save().await?;
+				}
+				Ok(true)
+			}
+			None => { Ok(false) }
+		}
+	}
 
-						if server_status != STOPPED {
-							// no need for await
-							self.restart_in_place();
-						} else {
-							// TODO: update config here
-							//if  { }.	update_config(mc_server.create_config().await.unwrap()).await;
-						}
-					};
-					return (StatusCode::OK, Json(Some(mod_info)));
+	/// Remember mods installed from mod platform and save config, older file of the same project is moved to mod history
+	/// so server doesn't load both. Entry of mod which no longer exists is dropped
+	pub async fn record_mods(&mut self, mods: Vec<MinecraftMod>) -> Result<()> {
+		let mut recorded = std::mem::take(&mut self.mods);
+		let mut replaced = vec![];
+		recorded.retain(|old| {
+			let new = mods.iter().find(|it| {
+				it.file_name == old.file_name || matches!((&it.source, &old.source), (Some(a), Some(b)) if a.same_project(b))
+			});
+			match new {
+				Some(new) if new.file_name != old.file_name => {
+					replaced.push((old.clone(), new.file_name.clone()));
+					false
 				}
+				Some(_) => { false }
+				None => { true }
+			}
+		});
+		if !replaced.is_empty() {
+			let mut history = self.mod_history().await?;
+			for (old, new) in replaced {
+				if let Some((path, _)) = self.find_mod(&old.file_name).await? {
+					debug!("{} is replaced by {new}", old.file_name);
+					history.archive(&path, old, Some(new)).await?;
+				}
+			}
+			history.prune(get_config().await.mod_updates.history_size).await?;
+		}
+		let mut kept = Vec::with_capacity(recorded.len() + mods.len());
+		for old in recorded {
+			if self.find_mod(&old.file_name).await?.is_some() {
+				kept.push(old);
+			}
+		}
+		kept.extend(mods);
+		self.mods = kept;
+		self.save().await
+	}
+
+	/// Rename mod to enable or disable it, return None if mod is not found
+	pub async fn set_mod_enabled(&self, file_name: &str, enabled: bool) -> Result<Option<InstalledMod>> {
+		let Some((path, was_enabled)) = self.find_mod(file_name).await? else {
+			return Ok(None);
+		};
+		let mod_dir = self.mod_dir()?;
+		if was_enabled != enabled {
+			let target = if enabled { mod_dir.join(file_name) } else { mod_dir.join(format!("{file_name}{DISABLED_SUFFIX}")) };
+			rename(path, target).await?;
+		}
+		Ok(self.list_mods().await?.into_iter().find(|it| it.info.file_name == file_name))
+	}
+
+	/// Mods replaced by update
+	pub async fn mod_history(&self) -> Result<ModHistory> {
+		ModHistory::load(self.dir(".mod_history")?).await
+	}
+
+	/// Restore mod from history and remove file which replaced it, return None if entry is not found
+	pub async fn rollback_mod(&mut self, id: u64) -> Result<Option<MinecraftMod>> {
+		let mut history = self.mod_history().await?;
+		let Some((entry, archived)) = history.take(id).await? else {
+			return Ok(None);
+		};
+		if let Some(new) = &entry.replaced_by {
+			if let Some((path, _)) = self.find_mod(new).await? {
+				remove_file(path).await?;
 			}
+			self.mods.retain(|it| &it.file_name != new);
 		}
-		(StatusCode::OK, Json(None))
+		rename(archived, self.mod_dir()?.join(&entry.removed.file_name)).await?;
+		self.record_mods(vec![entry.removed.clone()]).await?;
+		Ok(Some(entry.removed))
+	}
+
+	/// Check enabled mods against loader and minecraft version of this instance
+	pub async fn validate_mods(&self) -> Result<Vec<ModIssue>> {
+		let mods = self.scan_mods(false).await?;
+		Ok(mod_validator::validate(&self.mod_type, &self.version, &mods))
+	}
+
+	/// Minecraft version (velocity version for velocity)
+	pub fn version(&self) -> &str {
+		&self.version
+	}
+
+	/// See [ModType::mod_folder]
+	pub fn mod_dir(&self) -> Result<PathBuf> {
+		self.dir(self.mod_type.mod_folder())
 	}
 
 	#[inline]
@@ -362,13 +697,40 @@ impl McInstance {
 		self._server_instance.as_ref().map(|it| Arc::clone(it))
 	}
 
+	/// Restart server unless it's not started or gracefully stopped, return bool: true if restarting
+	pub async fn restart_if_running(&mut self) -> bool {
+		match self._server_instance.as_ref() {
+			Some(server) if server.status().await != STOPPED => {
+				// no need for await
+				self.restart_in_place();
+				true
+			}
+			_ => { false }
+		}
+	}
+
 	pub fn restart_in_place(&mut self) -> JoinHandle<Result<()>> {
 		match &self._server_instance {
 			None => { unreachable!() }
 			Some(server) => {
 				let cfg = Arc::clone(&self.config);
 				let server = Arc::clone(server);
+				let instance = self.clone();
 				spawn(async move {
+					// pre-start check, it's pointless to start server which will crash while loading mods
+					let errors = match cfg.check_mods {
+						true => { instance.validate_mods().await? }
+						false => { vec![] }
+					};
+					let errors = errors.into_iter()
+						.filter(|it| it.severity == Severity::Error)
+						.collect::<Vec<_>>();
+					if !errors.is_empty() {
+						for issue in &errors {
+							error!("{}: {}", issue.file, issue.message);
+						}
+						bail!("{} has {} mod problems, see /instance/{}/mods/validate (set `check_mods: false` to start anyway)", instance.name, errors.len(), instance.name);
+					}
 					server.restart_in_place(move || {
 						Box::pin(async move {
 							cfg.spawn().await
@@ -379,38 +741,36 @@ impl McInstance {
 		}
 	}
 
-	pub async fn scan_mods(&self) -> Result<Vec<MinecraftMod>> {
-		let mod_dir = self.dir("mods")?;
-		if !mod_dir.exists() {
-			return Ok(Vec::new());
-		}
-		let files = crate::file_scanner::scan_files(mod_dir, |it| it.file_name().to_string_lossy().ends_with(".jar")).await?;
-		let file_infos = FuturesUnordered::new();
-		for x in files {
-			file_infos.push(MinecraftMod::try_parse(x));
-		}
-
-		let f = file_infos
-			.collect::<Vec<_>>()
-			.await
-			.into_iter()
-			.filter_map(|it| it.ok())
-			.collect();
-		Ok(f)
-	}
-
 	async fn download_server(&self) -> io::Result<()> {
 		self.mod_type.download_server(&new_client()?,
 		                              &self.version,
-		                              &self.config.directory).await
+		                              &self.config.directory,
+		                              &()).await
 	}
 }
 
+/// Plain file name of a jar, without any path component
+fn is_mod_file_name(name: &str) -> bool {
+	name.ends_with(".jar") && !name.contains(['/', '\\']) && !name.starts_with('.')
+}
+
+pub(crate) fn bad_upload(err: MultipartError) -> ErrorWrapper {
+	warn!("Failed to read upload: {err}");
+	ErrorWrapper::custom(StatusCode::BAD_REQUEST, "Invalid multipart upload")
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub enum ModType {
 	Vanilla,
 	Purpur,
 	Forge(String),
+	NeoForge(String),
+	Paper,
+	Folia,
+	/// Velocity proxy; version is velocity version instead of minecraft version
+	Velocity,
+	/// Waterfall proxy; version is minecraft version it targets
+	Waterfall,
 }
 
 impl Default for ModType {
@@ -418,27 +778,141 @@ impl Default for ModType {
 }
 
 impl ModType {
-	pub async fn download_server(&self, client: &Client, mc_version: &str, target: impl AsRef<Path>) -> io::Result<()> {
-		let url = match self {
+	/// Name used in api path and cache
+	pub fn loader_name(&self) -> &'static str {
+		match self {
+			ModType::Vanilla => { "vanilla" }
+			ModType::Purpur => { "purpur" }
+			ModType::Forge(_) => { "forge" }
+			ModType::NeoForge(_) => { "neoforge" }
+			ModType::Paper => { "paper" }
+			ModType::Folia => { "folia" }
+			ModType::Velocity => { "velocity" }
+			ModType::Waterfall => { "waterfall" }
+		}
+	}
+
+	/// Reverse of [ModType::loader_name], loader version will be empty
+	pub fn from_loader_name(name: &str) -> Option<Self> {
+		Some(match name {
+			"vanilla" => { ModType::Vanilla }
+			"purpur" => { ModType::Purpur }
+			"forge" => { ModType::Forge(String::new()) }
+			"neoforge" => { ModType::NeoForge(String::new()) }
+			"paper" => { ModType::Paper }
+			"folia" => { ModType::Folia }
+			"velocity" => { ModType::Velocity }
+			"waterfall" => { ModType::Waterfall }
+			_ => { return None; }
+		})
+	}
+
+	/// Project name in PaperMC api
+	fn papermc_project(&self) -> Option<&'static str> {
+		match self {
+			ModType::Paper => { Some("paper") }
+			ModType::Folia => { Some("folia") }
+			ModType::Velocity => { Some("velocity") }
+			ModType::Waterfall => { Some("waterfall") }
+			_ => { None }
+		}
+	}
+
+	/// Folder where mods or plugins are loaded from
+	pub fn mod_folder(&self) -> &'static str {
+		match self {
+			ModType::Forge(_) | ModType::NeoForge(_) | ModType::Vanilla => { "mods" }
+			_ => { "plugins" }
+		}
+	}
+
+	/// Proxy doesn't run world itself, it forward player to backend servers
+	pub fn is_proxy(&self) -> bool {
+		matches!(self, ModType::Velocity | ModType::Waterfall)
+	}
+
+	pub fn console_profile(&self) -> ConsoleProfile {
+		match self {
+			ModType::Velocity => { ConsoleProfile::VELOCITY }
+			ModType::Waterfall => { ConsoleProfile::WATERFALL }
+			_ => { ConsoleProfile::MINECRAFT }
+		}
+	}
+
+	/// Installer artifact for forge-like loader
+	pub fn forge_artifact<'a>(&'a self, mc_version: &str) -> Option<ForgeArtifact<'a>> {
+		match self {
+			ModType::Forge(ver) => {
+				// forge version is prefixed with minecraft version eg. 1.20.1-47.2.0
+				let version = format!("{mc_version}-{ver}");
+				Some(ForgeArtifact {
+					repo: "https://maven.minecraftforge.net",
+					group: "net/minecraftforge",
+					name: "forge",
+					version: Cow::Owned(version),
+				})
+			}
+			ModType::NeoForge(ver) => {
+				Some(ForgeArtifact {
+					repo: "https://maven.neoforged.net/releases",
+					group: "net/neoforged",
+					name: "neoforge",
+					version: Cow::Borrowed(ver),
+				})
+			}
+			_ => { None }
+		}
+	}
+
+	pub async fn java_requirement(&self, version: &str) -> Option<JavaVersionForMc> {
+		if self.is_proxy() {
+			Some(PROXY.clone())
+		} else {
+			java_requirement(version).await
+		}
+	}
+
+	pub async fn download_server(&self, client: &Client, mc_version: &str, target: impl AsRef<Path>, progress: &dyn DownloadProgress) -> io::Result<()> {
+		let (url, expected) = match self {
 			ModType::Vanilla => {
 				return Err(io::Error::new(ErrorKind::Unsupported, anyhow!("Unsupported")));
 			}
+			ModType::Paper | ModType::Folia | ModType::Velocity | ModType::Waterfall => {
+				let Some(project) = self.papermc_project() else {
+					return Err(io::Error::new(ErrorKind::Unsupported, anyhow!("Unsupported")));
+				};
+				papermc::download(client, project, mc_version, target, progress).await?;
+				return Ok(());
+			}
 			ModType::Purpur => {
-				format!("https://api.purpurmc.org/v2/purpur/{mc_version}/latest/download")
+				// `latest` changes over time, resolve build number so it can be cached
+				let resp = client.get(format!("https://api.purpurmc.org/v2/purpur/{mc_version}"))
+					.send().await.and_then(|it| it.error_for_status()).map_err(reqwest_to_io)?;
+				let version: PurpurVersion = resp.json().await.map_err(reqwest_to_io)?;
+				let build = version.builds.latest;
+				let resp = client.get(format!("https://api.purpurmc.org/v2/purpur/{mc_version}/{build}"))
+					.send().await.and_then(|it| it.error_for_status()).map_err(reqwest_to_io)?;
+				let info: PurpurBuild = resp.json().await.map_err(reqwest_to_io)?;
+				(format!("https://api.purpurmc.org/v2/purpur/{mc_version}/{build}/download"), Some(FileHash::Md5(info.md5)))
 			}
-			ModType::Forge(ver) => {
-				format!("https://maven.minecraftforge.net/net/minecraftforge/forge/{mc_version}-{ver}/forge-{mc_version}-{ver}-installer.jar")
+			ModType::Forge(_) | ModType::NeoForge(_) => {
+				// only installer is downloaded, see `forge::install`
+				let artifact = self.forge_artifact(mc_version).unwrap();
+				(artifact.installer_url(), artifact.installer_hash(client).await)
 			}
 		};
-		download_to(client, &url, target).await?;
+		artifact_cache::fetch(client, &url, target, expected.as_ref(), progress).await?;
 		Ok(())
 	}
 
+	/// Fetch version list from remote, use [version_cache::versions] instead to avoid hitting remote every time
 	pub async fn versions(&self, client: &Client) -> io::Result<ModVersion> {
 		let mut res = ModVersion { versions: Default::default() };
 		match self {
 			ModType::Vanilla => {
-				return Err(io::Error::new(ErrorKind::Unsupported, anyhow!("Unsupported")));
+				res.versions = mojang::releases(false).await?.into_iter()
+					.map(|ver| (ver, ModVersionInfo { recommended: None, latest: String::new() }))
+					.collect();
 			}
 			ModType::Purpur => {
 				let resp = client.get("https://api.purpurmc.org/v2/purpur")
@@ -477,6 +951,37 @@ impl ModType {
 				}
 				res.versions = table;
 			}
+			ModType::NeoForge(_) => {
+				let resp = client.get("https://maven.neoforged.net/api/maven/versions/releases/net/neoforged/neoforge")
+					.send().await.map_err(reqwest_to_io)?;
+				let versions: NeoForgeVersions = resp.json().await.map_err(reqwest_to_io)?;
+				let mut table = HashMap::new();
+				// versions are sorted from oldest to newest
+				for ver in versions.versions {
+					let mc_ver = match neoforge_mc_version(&ver) {
+						Some(it) => { it }
+						None => { continue; }
+					};
+					let ent = table.entry(mc_ver)
+						.or_insert(ModVersionInfo {
+							recommended: None,
+							latest: String::new(),
+						});
+					if !ver.contains('-') {
+						ent.recommended = Some(ver.clone());
+					}
+					ent.latest = ver;
+				}
+				res.versions = table;
+			}
+			ModType::Paper | ModType::Folia | ModType::Velocity | ModType::Waterfall => {
+				let project = self.papermc_project().unwrap();
+				let mut table = HashMap::new();
+				for ver in papermc::versions(client, project).await? {
+					table.insert(ver, ModVersionInfo { recommended: None, latest: String::new() });
+				}
+				res.versions = table;
+			}
 		}
 		Ok(res)
 	}
@@ -488,25 +993,80 @@ struct PurpurVersions {
 	versions: Vec<String>,
 }
 
+#[derive(Deserialize)]
+struct PurpurVersion {
+	builds: PurpurBuilds,
+}
+
+#[derive(Deserialize)]
+struct PurpurBuilds {
+	latest: String,
+}
+
+#[derive(Deserialize)]
+struct PurpurBuild {
+	md5: String,
+}
+
 #[derive(Deserialize)]
 struct ForgeVersions {
 	homepage: String,
 	promos: HashMap<String, String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Deserialize)]
+struct NeoForgeVersions {
+	versions: Vec<String>,
+}
+
+/// NeoForge version start with minecraft version without `1.` prefix  
+/// eg. 20.4.80-beta = 1.20.4, 21.0.10 = 1.21; since 26.1 it start with full minecraft version (26.1.0.5 = 26.1)
+pub(crate) fn neoforge_mc_version(ver: &str) -> Option<String> {
+	let mut part = ver.split(['.', '-']);
+	let major = part.next()?.parse::<u32>().ok()?;
+	let minor = part.next()?.parse::<u32>().ok()?;
+	let patch = part.next().and_then(|it| it.parse::<u32>().ok());
+	Some(if major < 25 {
+		if minor == 0 { format!("1.{major}") } else { format!("1.{major}.{minor}") }
+	} else {
+		match patch {
+			Some(patch) if patch != 0 => { format!("{major}.{minor}.{patch}") }
+			_ => { format!("{major}.{minor}") }
+		}
+	})
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ModVersionInfo {
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	recommended: Option<String>,
 	latest: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ModVersion {
 	versions: HashMap<String, ModVersionInfo>,
 }
 
+/// Item of [ModVersion::sorted]
+#[derive(Serialize, Debug)]
+pub struct ModVersionEntry<'a> {
+	/// Minecraft version (proxy version for proxy)
+	pub version: &'a str,
+	#[serde(flatten)]
+	pub info: &'a ModVersionInfo,
+}
+
 impl ModVersion {
+	/// Every version, newest first
+	pub fn sorted(&self) -> Vec<ModVersionEntry<'_>> {
+		let mut list: Vec<ModVersionEntry> = self.versions.iter()
+			.map(|(version, info)| ModVersionEntry { version, info })
+			.collect();
+		list.sort_by(|a, b| cmp_semver(b.version, a.version));
+		list
+	}
+
 	pub fn latest(&self) -> Option<&str> {
 		self.versions.keys().max_by(|a, b| cmp_semver(a, b)).map(|it| it.as_str())
 	}
@@ -1,61 +1,241 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Seek};
 use std::path::Path;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use tokio::task::spawn_blocking;
+use zip::ZipArchive;
 
-use crate::get_manifest;
+use crate::jar_scanner::read_manifest;
+use crate::mc::mod_metadata::read_metadata;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MinecraftMod {
 	pub name: String,
 	pub version: String,
 	pub file_name: String,
+	/// Mod id from metadata (plugin name for bukkit)
+	#[serde(default, skip_serializing_if = "String::is_empty")]
+	pub id: String,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub authors: Vec<String>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub dependencies: Vec<ModDependency>,
+	/// Other mods declared in the same jar
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub provides: Vec<ProvidedMod>,
+	#[serde(default)]
+	pub side: ModSide,
+	/// None if jar doesn't have any known metadata
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub loader: Option<ModLoader>,
+	/// Where mod was installed from, used to check for updates
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub source: Option<ModSource>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProvidedMod {
+	pub id: String,
+	#[serde(default, skip_serializing_if = "String::is_empty")]
+	pub version: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "platform", rename_all = "lowercase")]
+pub enum ModSource {
+	Modrinth {
+		project_id: String,
+		version_id: String,
+	},
+	CurseForge {
+		project_id: u64,
+		file_id: u64,
+	},
+}
+
+impl ModSource {
+	/// Same project on the same platform
+	pub fn same_project(&self, other: &ModSource) -> bool {
+		match (self, other) {
+			(ModSource::Modrinth { project_id: a, .. }, ModSource::Modrinth { project_id: b, .. }) => { a == b }
+			(ModSource::CurseForge { project_id: a, .. }, ModSource::CurseForge { project_id: b, .. }) => { a == b }
+			_ => { false }
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ModLoader {
+	Forge,
+	NeoForge,
+	Fabric,
+	Quilt,
+	/// Bukkit/Spigot/Paper plugin
+	Bukkit,
+}
+
+/// Where mod need to be installed
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ModSide {
+	#[default]
+	Both,
+	Client,
+	Server,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+	#[default]
+	Required,
+	Optional,
+	/// Can't be installed together
+	Incompatible,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ModDependency {
+	pub id: String,
+	/// Version range as declared by mod, its syntax depends on loader (eg. `[47,)` or `>=0.14`); empty for any version
+	#[serde(default, skip_serializing_if = "String::is_empty")]
+	pub version_range: String,
+	#[serde(default)]
+	pub kind: DependencyKind,
+	#[serde(default)]
+	pub side: ModSide,
+}
+
+/// Mod file found in instance `mods` folder
+#[derive(Serialize, Debug, Clone)]
+pub struct InstalledMod {
+	#[serde(flatten)]
+	pub info: MinecraftMod,
+	/// Disabled mod is renamed to `<file>.jar.disabled` so loader skip it
+	pub enabled: bool,
+	pub sha256: String,
+	pub size: u64,
+	/// Why jar can't be read, its info is taken from file name
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+/// Suffix of mod file which is ignored by loader
+pub static DISABLED_SUFFIX: &str = ".disabled";
+
 impl MinecraftMod {
 	pub async fn try_parse(path: impl AsRef<Path>) -> Result<Self> {
-		let path = path.as_ref();
-		let manifest = get_manifest(path).await?;
+		let path = path.as_ref().to_path_buf();
+		spawn_blocking(move || Self::parse_jar(&path)).await?
+	}
+
+	fn parse_jar(path: &Path) -> Result<Self> {
+		let zip = ZipArchive::new(File::open(path)?)?;
 		let file_name = path.file_name().map(|it| it.to_string_lossy()).unwrap_or_default();
+		Ok(Self::parse_zip(zip, &file_name))
+	}
+
+	/// Mod of jar that can't be read
+	pub fn unknown(file_name: &str) -> Self {
+		Self {
+			name: file_name.split('-').next().unwrap_or_default().to_string(),
+			version: String::new(),
+			file_name: file_name.to_string(),
+			id: String::new(),
+			authors: vec![],
+			dependencies: vec![],
+			provides: vec![],
+			side: ModSide::Both,
+			loader: None,
+			source: None,
+		}
+	}
 
-		let name = manifest
-			.get("Specification-Title")
-			.map(|it| it.to_string())
+	pub(crate) fn parse_zip<R: Read + Seek>(mut zip: ZipArchive<R>, file_name: &str) -> Self {
+		let manifest = read_manifest(&mut zip).ok();
+		let mut metadata = read_metadata(&mut zip).into_iter();
+		let attr = |key: &str| manifest.as_ref().and_then(|it| it.get(key)).filter(|it| !it.is_empty()).cloned();
+
+		let name = attr("Specification-Title")
+			.or_else(|| attr("Implementation-Title"))
 			.unwrap_or_else(|| {
-				manifest
-					.get("Implementation-Title")
-					.map(|it| it.to_string())
-					.unwrap_or_else(|| {
-						let file_name = file_name.split('-').next();
-						file_name.unwrap_or_default().to_string()
-					})
+				let file_name = file_name.split('-').next();
+				file_name.unwrap_or_default().to_string()
 			});
-		Ok(Self {
-			name,
-			version: manifest.get("Implementation-Version").map(|it| it.to_string()).unwrap_or_else(|| {
-				let mut part = file_name.splitn(2, '-');
-				part.next();
-				let version = part.next();
-				version.unwrap_or_default().to_string()
-			}),
+		let version = attr("Implementation-Version").unwrap_or_else(|| {
+			let mut part = file_name.splitn(2, '-');
+			part.next();
+			let version = part.next();
+			version.unwrap_or_default().to_string()
+		});
+		// forge use placeholder that replaced by version in manifest
+		let version_or_jar = |it: String| if it.is_empty() || it.starts_with("${") { version.clone() } else { it };
+		let Some(main) = metadata.next() else {
+			return Self {
+				name,
+				version,
+				file_name: file_name.to_string(),
+				id: String::new(),
+				authors: vec![],
+				dependencies: vec![],
+				provides: vec![],
+				side: ModSide::Both,
+				loader: None,
+				source: None,
+			};
+		};
+		let mut dependencies = main.dependencies;
+		let mut provides = vec![];
+		for it in metadata {
+			dependencies.extend(it.dependencies);
+			provides.push(ProvidedMod { id: it.id, version: version_or_jar(it.version) });
+		}
+		// dependencies between mods of the same jar are always satisfied
+		dependencies.retain(|dep| dep.id != main.id && provides.iter().all(|it| it.id != dep.id));
+		let mut seen = HashSet::new();
+		dependencies.retain(|dep| seen.insert(dep.id.clone()));
+		Self {
+			name: if main.name.is_empty() { name } else { main.name },
+			version: version_or_jar(main.version),
 			file_name: file_name.to_string(),
-		})
+			id: main.id,
+			authors: main.authors,
+			dependencies,
+			provides,
+			side: main.side,
+			loader: Some(main.loader),
+			source: None,
+		}
+	}
+
+	/// Id and version of main mod and other mods in the same jar
+	pub fn ids(&self) -> impl Iterator<Item = (&str, &str)> {
+		Some((self.id.as_str(), self.version.as_str())).into_iter()
+			.filter(|(id, _)| !id.is_empty())
+			.chain(self.provides.iter().map(|it| (it.id.as_str(), it.version.as_str())))
 	}
 
-	pub fn diff_mod<'a, 'b>(old: &'a Vec<MinecraftMod>, recent: &'b Vec<MinecraftMod>) -> ModDiff<'b, 'a> {
+	/// Compare mod files, file with the same name but different source (eg. upgraded in place) is both removed and added
+	pub fn diff_mod<'a, 'b>(old: &'a [MinecraftMod], recent: &'b [MinecraftMod]) -> ModDiff<'b, 'a> {
 		let mut to_add = Vec::new();
 		let mut existing = HashMap::new();
 
 		for mc_mod in old {
-			existing.insert(&mc_mod.name, mc_mod);
+			existing.insert(&mc_mod.file_name, mc_mod);
 		}
 
 		for x in recent {
-			if let Some(m) = existing.get(&x.name) {
-				existing.remove(&m.name);
-			} else {
-				to_add.push(x);
+			match existing.get(&x.file_name) {
+				Some(m) if m.source == x.source => {
+					existing.remove(&x.file_name);
+				}
+				_ => {
+					to_add.push(x);
+				}
 			}
 		}
 
@@ -64,6 +244,6 @@ impl MinecraftMod {
 }
 
 pub struct ModDiff<'a, 'b> {
-	to_add: Vec<&'a MinecraftMod>,
-	to_remove: Vec<&'b MinecraftMod>,
-}
\ No newline at end of file
+	pub to_add: Vec<&'a MinecraftMod>,
+	pub to_remove: Vec<&'b MinecraftMod>,
+}
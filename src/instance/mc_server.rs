@@ -14,11 +14,45 @@ use crate::instance::mc_server::MinecraftServerStatus::{RUNNING, STARTING, STOPP
 
 pub struct MinecraftServer {
 	name: String,
+	profile: ConsoleProfile,
 	pub(crate) process: Arc<Mutex<Option<Child>>>,
 	pub(crate) stdin: RwLock<Option<BufWriter<ChildStdin>>>,
 	pub(crate) status: Arc<RwLock<MinecraftServerStatus>>,
 }
 
+/// Console behaviour that differs between server software
+#[derive(Debug, Copy, Clone)]
+pub struct ConsoleProfile {
+	/// Part of output line that printed once server is ready
+	pub ready_message: &'static str,
+	/// Command prefix used to broadcast message; None if software doesn't have one
+	pub say_command: Option<&'static str>,
+	/// Command to gracefully stop server
+	pub stop_command: &'static str,
+}
+
+impl ConsoleProfile {
+	pub const MINECRAFT: ConsoleProfile = ConsoleProfile {
+		ready_message: r#"For help, type "help""#,
+		say_command: Some("say "),
+		stop_command: "stop",
+	};
+
+	pub const VELOCITY: ConsoleProfile = ConsoleProfile {
+		// [INFO]: Done (1.23s)!
+		ready_message: "Done (",
+		say_command: None,
+		stop_command: "shutdown",
+	};
+
+	pub const WATERFALL: ConsoleProfile = ConsoleProfile {
+		// [INFO] Listening on /0.0.0.0:25577
+		ready_message: "Listening on /",
+		say_command: Some("alert "),
+		stop_command: "end",
+	};
+}
+
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum MinecraftServerStatus {
 	STARTING,
@@ -28,7 +62,7 @@ pub enum MinecraftServerStatus {
 }
 
 impl MinecraftServer {
-	pub fn new(name: String, process: Option<Child>) -> Result<Self> {
+	pub fn new(name: String, profile: ConsoleProfile, process: Option<Child>) -> Result<Self> {
 		if let Some(mut process) = process {
 			let stdout = process.stdout.take().unwrap();
 			let stdin = BufWriter::new(process.stdin.take().unwrap());
@@ -39,12 +73,13 @@ impl MinecraftServer {
 			let process_clone = process.clone();
 
 			trace!("starting server");
-			let this = Self { name, process, stdin: RwLock::new(Some(stdin)), status };
+			let this = Self { name, profile, process, stdin: RwLock::new(Some(stdin)), status };
 			this.create_heartbeat(stdout, status_clone, process_clone);
 			Ok(this)
 		} else {
 			Ok(Self {
 				name,
+				profile,
 				process: Arc::new(Mutex::new(None)),
 				stdin: RwLock::new(None),
 				status: Arc::new(RwLock::new(STOPPED)),
@@ -54,6 +89,7 @@ impl MinecraftServer {
 
 	pub(crate) fn create_heartbeat(&self, stdout: ChildStdout, status: Arc<RwLock<MinecraftServerStatus>>, process_clone: Arc<Mutex<Option<Child>>>) {
 		trace!("spawning heartbeat task");
+		let ready_message = self.profile.ready_message;
 		tokio::spawn(async move {
 			let pid = {
 				*status.write().await = STARTING;
@@ -70,7 +106,7 @@ impl MinecraftServer {
 				if let Ok(res) = timeout(Duration::from_secs(30), stdout.next_line()).await {
 					if let Ok(Some(line)) = res {
 						println!("{}", line);
-						if line.contains(r#"For help, type "help""#) {
+						if line.contains(ready_message) {
 							info!("found help message; server started!");
 							let mut s = status.write().await;
 							if *s == STOPPED {
@@ -171,9 +207,13 @@ impl MinecraftServer {
 	}
 
 	pub async fn say(&self, message: impl AsRef<str>) -> Result<()> {
+		let say = match self.profile.say_command {
+			Some(it) => { it }
+			None => { return Ok(()); }
+		};
 		let data = message.as_ref();
-		let mut msg = String::with_capacity(5 + data.as_bytes().len());
-		msg.push_str("say ");
+		let mut msg = String::with_capacity(say.len() + 1 + data.as_bytes().len());
+		msg.push_str(say);
 		msg.push_str(data);
 		msg.push('\n');
 		let mut stdin = self.stdin.write().await;
@@ -216,23 +256,23 @@ impl MinecraftServer {
 		let status = self.status().await;
 
 		if let (Some(mut stdin), true) = (stdin, soft) {
-			if status == RUNNING || status == STARTING {
+			if let (Some(say), true) = (self.profile.say_command, status == RUNNING || status == STARTING) {
 				debug!("Server is running! stop event will wait for 15 seconds");
-				stdin.write_all("say Server will stop within 15 seconds\n".as_bytes()).await?;
+				stdin.write_all(format!("{say}Server will stop within 15 seconds\n").as_bytes()).await?;
 				stdin.flush().await?;
 				let mut no = String::with_capacity(8);
 				use std::fmt::Write;
 				for counter in (0..=14).rev() {
 					sleep(Duration::from_secs(1)).await;
 					no.clear();
-					no.push_str("say ");
+					no.push_str(say);
 					writeln!(&mut no, "{}", counter).ok();
 					stdin.write_all(no.as_bytes()).await?;
 					stdin.flush().await?;
 				}
 			}
 
-			stdin.write_all("stop\n".as_bytes()).await?;
+			stdin.write_all(format!("{}\n", self.profile.stop_command).as_bytes()).await?;
 			stdin.flush().await?;
 			sleep(Duration::from_secs(1)).await;
 		} else if soft {
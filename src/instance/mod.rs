@@ -1,3 +1,8 @@
+pub mod manifest;
 pub mod mc_instance;
 pub mod mc_mod;
 pub mod mc_server;
+pub mod mod_history;
+pub mod mod_validator;
+pub mod server_import;
+pub mod zip_cache;
@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::fs::{create_dir_all, read, remove_file, rename};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+use crate::instance::mc_mod::MinecraftMod;
+use crate::util::fs::write_file;
+
+/// Mod file replaced by update, kept so it can be rolled back
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+	pub id: u64,
+	/// Unix time in seconds
+	pub time: u64,
+	/// Mod as it was installed, [MinecraftMod::file_name] is where it's restored to
+	#[serde(rename = "mod")]
+	pub removed: MinecraftMod,
+	/// File name of mod which replaced it
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub replaced_by: Option<String>,
+}
+
+/// One lock per history folder, every change reloads `history.json` under it so concurrent update and rollback don't
+/// overwrite each other
+static HISTORY_LOCK: Mutex<BTreeMap<PathBuf, Arc<Mutex<()>>>> = Mutex::const_new(BTreeMap::new());
+
+/// Per-instance folder of replaced mods, index is stored in `history.json`
+pub struct ModHistory {
+	folder: PathBuf,
+	entries: Vec<HistoryEntry>,
+}
+
+impl ModHistory {
+	pub async fn load(folder: impl Into<PathBuf>) -> Result<Self> {
+		let mut history = Self { folder: folder.into(), entries: vec![] };
+		let guard = history.lock().await;
+		history.reload().await?;
+		drop(guard);
+		Ok(history)
+	}
+
+	async fn reload(&mut self) -> Result<()> {
+		self.entries = match read(self.folder.join("history.json")).await {
+			Ok(data) => { serde_json::from_slice(&data)? }
+			Err(_) => { vec![] }
+		};
+		Ok(())
+	}
+
+	async fn lock(&self) -> OwnedMutexGuard<()> {
+		let lock = Arc::clone(HISTORY_LOCK.lock().await.entry(self.folder.clone()).or_default());
+		lock.lock_owned().await
+	}
+
+	async fn save(&self) -> Result<()> {
+		write_file(self.folder.join("history.json"), serde_json::to_vec_pretty(&self.entries)?).await?;
+		Ok(())
+	}
+
+	/// Newest last
+	pub fn entries(&self) -> &[HistoryEntry] {
+		&self.entries
+	}
+
+	fn archived(&self, entry: &HistoryEntry) -> PathBuf {
+		self.folder.join(format!("{}-{}", entry.id, entry.removed.file_name))
+	}
+
+	/// Move mod file into history, see [Self::prune]
+	pub async fn archive(&mut self, file: &Path, removed: MinecraftMod, replaced_by: Option<String>) -> Result<u64> {
+		let _guard = self.lock().await;
+		self.reload().await?;
+		create_dir_all(&self.folder).await?;
+		let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+		// keep id unique when archiving multiple files at once
+		let id = self.entries.last().map_or(0, |it| it.id + 1).max(now.as_millis() as u64);
+		let entry = HistoryEntry { id, time: now.as_secs(), removed, replaced_by };
+		rename(file, self.archived(&entry)).await?;
+		self.entries.push(entry);
+		self.save().await?;
+		Ok(id)
+	}
+
+	/// Drop the oldest entries if there are more than `limit`
+	pub async fn prune(&mut self, limit: usize) -> Result<()> {
+		let _guard = self.lock().await;
+		self.reload().await?;
+		if self.entries.len() <= limit.max(1) {
+			return Ok(());
+		}
+		while self.entries.len() > limit.max(1) {
+			let old = self.entries.remove(0);
+			remove_file(self.archived(&old)).await.ok();
+		}
+		self.save().await
+	}
+
+	/// Remove entry from history, return it with path of archived file which has to be moved back by caller
+	pub async fn take(&mut self, id: u64) -> Result<Option<(HistoryEntry, PathBuf)>> {
+		let _guard = self.lock().await;
+		self.reload().await?;
+		let Some(index) = self.entries.iter().position(|it| it.id == id) else {
+			return Ok(None);
+		};
+		let entry = self.entries.remove(index);
+		let path = self.archived(&entry);
+		self.save().await?;
+		Ok(Some((entry, path)))
+	}
+}
@@ -0,0 +1,400 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use hashbrown::HashMap;
+use serde::Serialize;
+
+use crate::instance::mc_instance::ModType;
+use crate::instance::mc_mod::{DependencyKind, InstalledMod, ModLoader, ModSide};
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+	/// Server will likely fail to start
+	Error,
+	Warning,
+}
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+	MissingDependency,
+	VersionMismatch,
+	DuplicateId,
+	Incompatible,
+	ClientOnly,
+	WrongLoader,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ModIssue {
+	pub severity: Severity,
+	pub kind: IssueKind,
+	/// Mod file which has the problem
+	pub file: String,
+	/// Related mod id (dependency or duplicated id)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub related: Option<String>,
+	pub message: String,
+}
+
+/// Ids provided by loader itself, they are never installed as jar
+fn builtin_versions(mod_type: &ModType, mc_version: &str) -> HashMap<&'static str, String> {
+	let mut table = HashMap::new();
+	table.insert("minecraft", mc_version.to_string());
+	match mod_type {
+		ModType::Forge(ver) => {
+			table.insert("forge", ver.clone());
+		}
+		ModType::NeoForge(ver) => {
+			table.insert("neoforge", ver.clone());
+			// neoforge still provide forge for old mods
+			table.insert("forge", ver.clone());
+		}
+		_ => {}
+	}
+	table
+}
+
+/// Dependency which can't be checked (java, mixin etc.)
+fn ignored_dependency(id: &str) -> bool {
+	matches!(id, "java" | "mixinextras" | "fabricloader" | "quilt_loader")
+}
+
+fn loader_accepted(mod_type: &ModType, loader: ModLoader) -> bool {
+	match mod_type {
+		ModType::Forge(_) => { loader == ModLoader::Forge }
+		ModType::NeoForge(_) => { matches!(loader, ModLoader::NeoForge | ModLoader::Forge) }
+		ModType::Purpur | ModType::Paper | ModType::Folia | ModType::Waterfall => { loader == ModLoader::Bukkit }
+		ModType::Vanilla | ModType::Velocity => { false }
+	}
+}
+
+/// Check installed mods (disabled mods are skipped) against each other and the server they are installed to
+pub fn validate(mod_type: &ModType, mc_version: &str, mods: &[InstalledMod]) -> Vec<ModIssue> {
+	let mods: Vec<&InstalledMod> = mods.iter().filter(|it| it.enabled).collect();
+	let mut issues = vec![];
+	let builtin = builtin_versions(mod_type, mc_version);
+
+	// ordered so issues are the same on every call; ids are case-insensitive (legacy forge declares `Forge`)
+	let mut by_id: BTreeMap<String, Vec<(&InstalledMod, &str)>> = BTreeMap::new();
+	for m in &mods {
+		for (id, version) in m.info.ids() {
+			by_id.entry(id.to_ascii_lowercase()).or_default().push((m, version));
+		}
+	}
+	for (id, files) in &by_id {
+		if files.len() > 1 {
+			for (m, _) in files {
+				issues.push(ModIssue {
+					severity: Severity::Error,
+					kind: IssueKind::DuplicateId,
+					file: m.info.file_name.clone(),
+					related: Some(id.to_string()),
+					message: format!("{id} is installed {} times", files.len()),
+				});
+			}
+		}
+	}
+
+	for m in &mods {
+		let file = &m.info.file_name;
+		if let Some(loader) = m.info.loader {
+			if !loader_accepted(mod_type, loader) {
+				issues.push(ModIssue {
+					severity: Severity::Error,
+					kind: IssueKind::WrongLoader,
+					file: file.clone(),
+					related: None,
+					message: format!("{loader:?} mod can't be loaded by {}", mod_type.loader_name()),
+				});
+				// its dependencies are meaningless for this server
+				continue;
+			}
+		}
+		if m.info.side == ModSide::Client {
+			issues.push(ModIssue {
+				severity: Severity::Warning,
+				kind: IssueKind::ClientOnly,
+				file: file.clone(),
+				related: None,
+				message: format!("{} is client only mod", m.info.name),
+			});
+		}
+		for dep in &m.info.dependencies {
+			let id = dep.id.to_ascii_lowercase();
+			if dep.side == ModSide::Client || ignored_dependency(&id) {
+				continue;
+			}
+			let installed = builtin.get(id.as_str()).map(|it| it.as_str())
+				.or_else(|| by_id.get(&id).and_then(|it| it.first()).map(|(_, version)| *version));
+			let in_range = installed.map(|ver| VersionRange::parse(&dep.version_range).matches(ver));
+			match (dep.kind, installed, in_range) {
+				(DependencyKind::Required, None, _) => {
+					issues.push(ModIssue {
+						severity: Severity::Error,
+						kind: IssueKind::MissingDependency,
+						file: file.clone(),
+						related: Some(dep.id.clone()),
+						message: format!("{} requires {} {}", m.info.name, dep.id, dep.version_range),
+					});
+				}
+				(DependencyKind::Required | DependencyKind::Optional, Some(ver), Some(false)) => {
+					issues.push(ModIssue {
+						severity: if dep.kind == DependencyKind::Required { Severity::Error } else { Severity::Warning },
+						kind: IssueKind::VersionMismatch,
+						file: file.clone(),
+						related: Some(dep.id.clone()),
+						message: format!("{} requires {} {} but {ver} is installed", m.info.name, dep.id, dep.version_range),
+					});
+				}
+				(DependencyKind::Incompatible, Some(ver), Some(true)) => {
+					issues.push(ModIssue {
+						severity: Severity::Error,
+						kind: IssueKind::Incompatible,
+						file: file.clone(),
+						related: Some(dep.id.clone()),
+						message: format!("{} is incompatible with {} {ver}", m.info.name, dep.id),
+					});
+				}
+				_ => {}
+			}
+		}
+	}
+	issues
+}
+
+/// Version range in maven (forge, eg. `[47,48)`) or fabric syntax (eg. `>=0.14 <0.15 || 1.20.x`).
+/// Anything that can't be parsed matches every version
+#[derive(Debug, PartialEq)]
+enum VersionRange {
+	Any,
+	/// Any of ranges (lower, lower inclusive, upper, upper inclusive)
+	Maven(Vec<(Option<String>, bool, Option<String>, bool)>),
+	/// Any of predicate groups, every predicate in group must match
+	Predicates(Vec<Vec<(Ordering, bool, String)>>),
+}
+
+impl VersionRange {
+	fn parse(range: &str) -> Self {
+		let range = range.trim();
+		if range.is_empty() || range == "*" {
+			return Self::Any;
+		}
+		if range.starts_with(['[', '(']) {
+			return Self::parse_maven(range).unwrap_or(Self::Any);
+		}
+		Self::parse_predicates(range).unwrap_or(Self::Any)
+	}
+
+	fn parse_maven(range: &str) -> Option<Self> {
+		let mut ranges = vec![];
+		let mut rest = range;
+		while !rest.is_empty() {
+			let lower_inclusive = rest.starts_with('[');
+			let end = rest.find([']', ')'])?;
+			let upper_inclusive = &rest[end..end + 1] == "]";
+			let body = &rest[1..end];
+			let bound = |it: &str| Some(it.trim().to_string()).filter(|it| !it.is_empty());
+			match body.split_once(',') {
+				Some((lower, upper)) => {
+					ranges.push((bound(lower), lower_inclusive, bound(upper), upper_inclusive));
+				}
+				// [1.0] is exact version
+				None => {
+					ranges.push((bound(body), true, bound(body), true));
+				}
+			}
+			rest = rest[end + 1..].trim_start_matches([',', ' ']);
+		}
+		Some(Self::Maven(ranges))
+	}
+
+	fn parse_predicates(range: &str) -> Option<Self> {
+		let mut groups = vec![];
+		for group in range.split("||") {
+			let mut predicates = vec![];
+			for predicate in group.split_whitespace() {
+				let (op, ver) = match predicate.find(|it: char| it.is_ascii_alphanumeric()) {
+					Some(i) => { predicate.split_at(i) }
+					None => { continue; }
+				};
+				let ver = ver.trim_end_matches(".x").trim_end_matches(".*");
+				match op {
+					">=" => { predicates.push((Ordering::Greater, true, ver.to_string())) }
+					">" => { predicates.push((Ordering::Greater, false, ver.to_string())) }
+					"<=" => { predicates.push((Ordering::Less, true, ver.to_string())) }
+					"<" => { predicates.push((Ordering::Less, false, ver.to_string())) }
+					// same major version
+					"^" => {
+						let major: u64 = ver.split('.').next()?.parse().ok()?;
+						predicates.push((Ordering::Greater, true, ver.to_string()));
+						predicates.push((Ordering::Less, false, (major + 1).to_string()));
+					}
+					// same minor version
+					"~" => {
+						let mut part = ver.split('.');
+						let major = part.next()?;
+						let minor: u64 = part.next().unwrap_or("0").parse().ok()?;
+						predicates.push((Ordering::Greater, true, ver.to_string()));
+						predicates.push((Ordering::Less, false, format!("{major}.{}", minor + 1)));
+					}
+					// `1.20.x` is prefix match
+					"" | "=" if predicate.ends_with(".x") || predicate.ends_with(".*") => {
+						predicates.push((Ordering::Equal, false, ver.to_string()));
+					}
+					"" | "=" => { predicates.push((Ordering::Equal, true, ver.to_string())) }
+					_ => { return None; }
+				}
+			}
+			groups.push(predicates);
+		}
+		Some(Self::Predicates(groups))
+	}
+
+	fn matches(&self, version: &str) -> bool {
+		match self {
+			VersionRange::Any => { true }
+			VersionRange::Maven(ranges) => {
+				ranges.iter().any(|(lower, lower_inclusive, upper, upper_inclusive)| {
+					let lower_ok = lower.as_ref().map_or(true, |it| match compare_versions(version, it) {
+						Ordering::Greater => { true }
+						Ordering::Equal => { *lower_inclusive }
+						Ordering::Less => { false }
+					});
+					let upper_ok = upper.as_ref().map_or(true, |it| match compare_versions(version, it) {
+						Ordering::Less => { true }
+						Ordering::Equal => { *upper_inclusive }
+						Ordering::Greater => { false }
+					});
+					lower_ok && upper_ok
+				})
+			}
+			VersionRange::Predicates(groups) => {
+				groups.iter().any(|group| group.iter().all(|(ord, inclusive, ver)| {
+					match (ord, inclusive) {
+						// prefix match
+						(Ordering::Equal, false) => { version == ver || version.starts_with(&format!("{ver}.")) }
+						(ord, inclusive) => {
+							let res = compare_versions(version, ver);
+							res == *ord || (*inclusive && res == Ordering::Equal)
+						}
+					}
+				}))
+			}
+		}
+	}
+}
+
+/// Compare dot separated versions, missing part is treated as 0 and pre-release (`-beta`) is older than release.
+/// Build metadata after `+` is ignored
+fn compare_versions(left: &str, right: &str) -> Ordering {
+	fn split(ver: &str) -> (&str, Option<&str>) {
+		let ver = ver.split('+').next().unwrap_or_default();
+		match ver.split_once('-') {
+			Some((main, pre)) => { (main, Some(pre)) }
+			None => { (ver, None) }
+		}
+	}
+	fn cmp_part(a: &str, b: &str) -> Ordering {
+		match (a.parse::<u64>(), b.parse::<u64>()) {
+			(Ok(a), Ok(b)) => { a.cmp(&b) }
+			_ => { a.cmp(b) }
+		}
+	}
+	let (left_main, left_pre) = split(left);
+	let (right_main, right_pre) = split(right);
+	let mut left_part = left_main.split('.');
+	let mut right_part = right_main.split('.');
+	loop {
+		let ord = match (left_part.next(), right_part.next()) {
+			(None, None) => { break; }
+			(a, b) => { cmp_part(a.unwrap_or("0"), b.unwrap_or("0")) }
+		};
+		if ord != Ordering::Equal {
+			return ord;
+		}
+	}
+	match (left_pre, right_pre) {
+		(None, None) => { Ordering::Equal }
+		(None, Some(_)) => { Ordering::Greater }
+		(Some(_), None) => { Ordering::Less }
+		(Some(a), Some(b)) => { a.split('.').cmp(b.split('.')) }
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::instance::mc_instance::ModType;
+	use crate::instance::mc_mod::{InstalledMod, MinecraftMod, ModDependency, ModLoader, ModSide, ProvidedMod};
+	use crate::instance::mod_validator::{IssueKind, validate, VersionRange};
+
+	fn installed(id: &str, version: &str, dependencies: &[(&str, &str)]) -> InstalledMod {
+		InstalledMod {
+			info: MinecraftMod {
+				name: id.to_string(),
+				version: version.to_string(),
+				file_name: format!("{id}-{version}.jar"),
+				id: id.to_string(),
+				authors: vec![],
+				dependencies: dependencies.iter()
+					.map(|(id, range)| ModDependency { id: id.to_string(), version_range: range.to_string(), kind: Default::default(), side: ModSide::Both })
+					.collect(),
+				provides: vec![],
+				side: ModSide::Both,
+				loader: Some(ModLoader::Forge),
+				source: None,
+			},
+			enabled: true,
+			sha256: String::new(),
+			size: 0,
+			error: None,
+		}
+	}
+
+	#[test]
+	fn test_validate() {
+		let forge = ModType::Forge("14.23.5.2860".to_string());
+		// legacy mcmod.info declares `Forge`
+		let jei = installed("jei", "4.16.1", &[("Forge", "[14.23.5,)")]);
+		assert!(validate(&forge, "1.12.2", &[jei.clone()]).is_empty());
+		let issues = validate(&ModType::Forge("14.23.4.2768".to_string()), "1.12.2", &[jei]);
+		assert_eq!(issues[0].kind, IssueKind::VersionMismatch);
+
+		// dependency provided by second mod of another jar
+		let mut create = installed("create", "0.5.1", &[("forge", "[47,)")]);
+		create.info.provides.push(ProvidedMod { id: "ponder".to_string(), version: "0.6".to_string() });
+		let addon = installed("createaddition", "1.2", &[("ponder", "[0.5,)"), ("curios", "")]);
+		let issues = validate(&ModType::Forge("47.2.0".to_string()), "1.20.1", &[create.clone(), addon]);
+		assert_eq!(issues.len(), 1);
+		assert_eq!(issues[0].kind, IssueKind::MissingDependency);
+		assert_eq!(issues[0].related.as_deref(), Some("curios"));
+
+		let ponder = installed("Ponder", "0.6", &[]);
+		let issues = validate(&ModType::Forge("47.2.0".to_string()), "1.20.1", &[create, ponder]);
+		assert_eq!(issues.len(), 2);
+		assert!(issues.iter().all(|it| it.kind == IssueKind::DuplicateId));
+	}
+
+	#[test]
+	fn test_range() {
+		let forge = VersionRange::parse("[47,48)");
+		assert!(forge.matches("47.2.0"));
+		assert!(!forge.matches("48.0.1"));
+		assert!(!forge.matches("46.0.0"));
+		assert!(VersionRange::parse("[1.20.1]").matches("1.20.1"));
+		assert!(VersionRange::parse("[1.0,2.0),[3.0,)").matches("3.1"));
+		assert!(!VersionRange::parse("[1.0,2.0),[3.0,)").matches("2.5"));
+		assert!(VersionRange::parse("[20.4.80-beta,)").matches("20.4.80"));
+
+		assert!(VersionRange::parse(">=0.14 <0.15").matches("0.14.21"));
+		assert!(!VersionRange::parse(">=0.14 <0.15").matches("0.15.0"));
+		assert!(VersionRange::parse("1.20.3 || 1.20.4").matches("1.20.4"));
+		assert!(VersionRange::parse("1.20.x").matches("1.20.6"));
+		assert!(!VersionRange::parse("1.20.x").matches("1.21"));
+		assert!(VersionRange::parse("~1.20").matches("1.20.1"));
+		assert!(!VersionRange::parse("^1.2").matches("2.0.0"));
+		assert!(VersionRange::parse(">=0.91.0+1.20.1").matches("0.92.2+1.20.1"));
+		assert!(VersionRange::parse("*").matches("anything"));
+	}
+}
@@ -0,0 +1,393 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::fs::{read_dir, read_to_string, rename};
+#[cfg(not(target_os = "windows"))]
+use tokio::fs::symlink;
+#[cfg(target_os = "windows")]
+use tokio::fs::symlink_dir as symlink;
+use tokio::task::spawn_blocking;
+use tracing::debug;
+use zip::ZipArchive;
+
+use crate::instance::mc_instance::{ModType, neoforge_mc_version};
+use crate::jar_scanner::get_manifest;
+use crate::mc::forge;
+use crate::mc::forge::ForgeLaunch;
+use crate::mc::mc_config::MinecraftConfig;
+
+/// Start scripts checked before any other script in server folder
+static SCRIPTS: &[&str] = &["run.sh", "start.sh", "launch.sh", "ServerStart.sh", "run.bat", "start.bat", "ServerStart.bat"];
+
+/// How existing server folder is placed under `instances`
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+	/// Folder stays where it is, instance is a link to it
+	#[default]
+	Symlink,
+	Move,
+}
+
+impl ImportMode {
+	pub async fn apply(self, source: &Path, target: &Path) -> Result<()> {
+		match self {
+			ImportMode::Symlink => {
+				symlink(source.canonicalize()?, target).await.with_context(|| format!("Failed to link {source:?}"))?;
+			}
+			ImportMode::Move => {
+				// rename doesn't work across filesystems, symlink should be used instead
+				rename(source, target).await.with_context(|| format!("Failed to move {source:?}"))?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Undo [Self::apply], it's blocking so it can also run when import job is dropped
+	pub fn revert(self, source: &Path, target: &Path) -> Result<()> {
+		match self {
+			ImportMode::Symlink => { std::fs::remove_file(target)?; }
+			ImportMode::Move => { std::fs::rename(target, source)?; }
+		}
+		Ok(())
+	}
+}
+
+/// Canonical path of folder if it's inside one of `roots` (root itself isn't allowed)
+pub fn inside_roots(path: &Path, roots: &[String]) -> Option<PathBuf> {
+	let path = path.canonicalize().ok()?;
+	roots.iter()
+		.filter_map(|it| Path::new(it).canonicalize().ok())
+		.any(|root| path != root && path.starts_with(root))
+		.then_some(path)
+}
+
+/// Server loader found in folder
+pub struct DetectedLoader {
+	pub mod_type: ModType,
+	/// Minecraft version (velocity version for velocity)
+	pub version: String,
+	/// Forge-like loader may launch from args file, other server is launched from its jar
+	pub launch: ForgeLaunch,
+}
+
+/// Java command found in start script
+#[derive(Debug, Default, PartialEq)]
+pub struct LaunchScript {
+	/// Java executable, only absolute path is kept
+	pub java: Option<String>,
+	pub jvm_args: Vec<String>,
+	/// Jar after `-jar`
+	pub server_file: Option<String>,
+	/// `@libraries/.../unix_args.txt` used instead of `-jar`
+	pub launch_args: Vec<String>,
+	pub args: Vec<String>,
+}
+
+impl LaunchScript {
+	/// Use java, memory and arguments from script; `-Xmx` becomes `max_ram`
+	pub fn apply(self, config: &mut MinecraftConfig) {
+		if let Some(java) = self.java {
+			config.java = java;
+		}
+		config.jvm_args = self.jvm_args.into_iter()
+			.filter(|it| match it.strip_prefix("-Xmx").and_then(parse_ram) {
+				Some(ram) => {
+					config.max_ram = ram;
+					false
+				}
+				None => { true }
+			})
+			.collect();
+		config.args = self.args;
+	}
+}
+
+/// Find java command in start script of server folder, `@file` in jvm arguments (eg. forge `user_jvm_args.txt`) is inlined
+pub async fn read_script(folder: &Path) -> Result<Option<LaunchScript>> {
+	let mut scripts: Vec<String> = SCRIPTS.iter().map(|it| it.to_string()).collect();
+	let mut dir = read_dir(folder).await?;
+	while let Some(ent) = dir.next_entry().await? {
+		let name = ent.file_name().to_string_lossy().to_string();
+		if [".sh", ".bat", ".cmd", ".command"].iter().any(|it| name.ends_with(it)) && !scripts.contains(&name) {
+			scripts.push(name);
+		}
+	}
+	for name in scripts {
+		let Ok(content) = read_to_string(folder.join(&name)).await else {
+			continue;
+		};
+		let Some(mut script) = parse_script(&content) else {
+			continue;
+		};
+		debug!("found launch command in {name}");
+		let mut jvm_args = Vec::with_capacity(script.jvm_args.len());
+		for arg in std::mem::take(&mut script.jvm_args) {
+			match arg.strip_prefix('@') {
+				Some(file) => {
+					match read_to_string(folder.join(file)).await {
+						Ok(content) => { jvm_args.extend(parse_args_file(&content)); }
+						Err(_) => { jvm_args.push(arg); }
+					}
+				}
+				None => { jvm_args.push(arg); }
+			}
+		}
+		script.jvm_args = jvm_args;
+		return Ok(Some(script));
+	}
+	Ok(None)
+}
+
+/// Detect loader from forge-like libraries or from manifest of jars in server folder.
+/// Jar or args file used by start script is preferred
+pub async fn detect_loader(folder: &Path, script: Option<&LaunchScript>) -> Result<DetectedLoader> {
+	let mut forge_like = detect_forge(folder).await?;
+	if !forge_like.is_empty() {
+		let used = forge_like.iter().position(|it| {
+			matches!((&it.launch, script), (ForgeLaunch::ArgsFile(file), Some(script)) if script.launch_args.iter().any(|arg| arg.strip_prefix('@') == Some(file)))
+		});
+		return Ok(forge_like.swap_remove(used.unwrap_or(forge_like.len() - 1)));
+	}
+
+	let mut jars = vec![];
+	let mut dir = read_dir(folder).await?;
+	while let Some(ent) = dir.next_entry().await? {
+		let name = ent.file_name().to_string_lossy().to_string();
+		if name.ends_with(".jar") && !name.contains("installer") && ent.file_type().await?.is_file() {
+			jars.push(name);
+		}
+	}
+	jars.sort();
+	if let Some(used) = script.and_then(|it| it.server_file.as_ref()) {
+		if let Some(pos) = jars.iter().position(|it| it == used) {
+			let used = jars.remove(pos);
+			jars.insert(0, used);
+		}
+	}
+	let mut vanilla = None;
+	for jar in jars {
+		let Some((mod_type, version)) = inspect_jar(&folder.join(&jar)).await? else {
+			continue;
+		};
+		debug!("{jar} is {} {version}", mod_type.loader_name());
+		let detected = DetectedLoader { mod_type, version, launch: ForgeLaunch::Jar(jar) };
+		// vanilla jar is often kept next to loader
+		if !matches!(detected.mod_type, ModType::Vanilla) {
+			return Ok(detected);
+		}
+		vanilla.get_or_insert(detected);
+	}
+	match vanilla {
+		Some(it) => { Ok(it) }
+		None => { bail!("Can't find server jar in {folder:?}") }
+	}
+}
+
+/// Forge and NeoForge installations in `libraries`
+async fn detect_forge(folder: &Path) -> Result<Vec<DetectedLoader>> {
+	let mut found = vec![];
+	for (group, is_forge) in [("libraries/net/minecraftforge/forge", true), ("libraries/net/neoforged/neoforge", false)] {
+		let Ok(mut dir) = read_dir(folder.join(group)).await else {
+			continue;
+		};
+		let mut versions = vec![];
+		while let Some(ent) = dir.next_entry().await? {
+			versions.push(ent.file_name().to_string_lossy().to_string());
+		}
+		versions.sort();
+		for ver in versions {
+			let (mod_type, version) = if is_forge {
+				// forge folder is `<minecraft>-<forge>`
+				let Some((mc, ver)) = ver.split_once('-') else {
+					continue;
+				};
+				(ModType::Forge(ver.to_string()), mc.to_string())
+			} else {
+				let Some(mc) = neoforge_mc_version(&ver) else {
+					continue;
+				};
+				(ModType::NeoForge(ver), mc)
+			};
+			let launch = match forge::detect_launch(folder, &mod_type.forge_artifact(&version).unwrap()).await {
+				Ok(it) => { it }
+				Err(_) => { continue; }
+			};
+			found.push(DetectedLoader { mod_type, version, launch });
+		}
+	}
+	Ok(found)
+}
+
+/// Loader and minecraft version from manifest of server jar, None if it's not a known server jar
+async fn inspect_jar(path: &Path) -> Result<Option<(ModType, String)>> {
+	let Ok(manifest) = get_manifest(path).await else {
+		return Ok(None);
+	};
+	let Some(main_class) = manifest.get("Main-Class") else {
+		return Ok(None);
+	};
+	let implementation = manifest.get("Implementation-Version").map(|it| it.as_str()).unwrap_or_default();
+	Ok(match main_class.as_str() {
+		"net.minecraft.bundler.Main" | "net.minecraft.server.Main" | "net.minecraft.server.MinecraftServer" => {
+			jar_version(path).await?.map(|ver| (ModType::Vanilla, ver))
+		}
+		"io.papermc.paperclip.Main" | "io.papermc.paperclip.Paperclip" | "com.destroystokyo.paperclip.Paperclip" => {
+			match read_jar_entry(path, "META-INF/versions.list").await?.as_deref().and_then(paperclip_version) {
+				Some(it) => { Some(it) }
+				// paperclip before 1.18 only has vanilla version
+				None => { jar_version(path).await?.map(|ver| (ModType::Paper, ver)) }
+			}
+		}
+		"com.velocitypowered.proxy.Velocity" => {
+			implementation.split_whitespace().next().map(|ver| (ModType::Velocity, ver.to_string()))
+		}
+		"net.md_5.bungee.Bootstrap" => {
+			waterfall_version(implementation).map(|ver| (ModType::Waterfall, ver))
+		}
+		it if it.starts_with("net.fabricmc.") || it.starts_with("org.quiltmc.") => {
+			bail!("Fabric and Quilt server aren't supported");
+		}
+		_ => { None }
+	})
+}
+
+/// `id` of `version.json` inside server jar
+async fn jar_version(path: &Path) -> Result<Option<String>> {
+	let Some(content) = read_jar_entry(path, "version.json").await? else {
+		return Ok(None);
+	};
+	let version: Value = serde_json::from_str(&content)?;
+	Ok(version.get("id").and_then(|it| it.as_str()).map(|it| it.to_string()))
+}
+
+async fn read_jar_entry(path: &Path, name: &'static str) -> Result<Option<String>> {
+	let path = path.to_path_buf();
+	spawn_blocking(move || {
+		let mut zip = ZipArchive::new(std::fs::File::open(path)?)?;
+		let mut entry = match zip.by_name(name) {
+			Ok(it) => { it }
+			Err(zip::result::ZipError::FileNotFound) => { return Ok(None); }
+			Err(err) => { return Err(err.into()); }
+		};
+		let mut content = String::new();
+		entry.read_to_string(&mut content)?;
+		Ok(Some(content))
+	}).await?
+}
+
+/// `META-INF/versions.list` of paperclip has `<sha256>\t<project>-<version>\t<path>`
+fn paperclip_version(list: &str) -> Option<(ModType, String)> {
+	let id = list.lines().next()?.split('\t').nth(1)?;
+	let (project, version) = id.split_once('-')?;
+	match ModType::from_loader_name(project)? {
+		it @ (ModType::Paper | ModType::Purpur | ModType::Folia) => { Some((it, version.to_string())) }
+		_ => { None }
+	}
+}
+
+/// Implementation version of waterfall eg. `git:Waterfall-Bootstrap:1.20-R0.1-SNAPSHOT:abc1234:570`
+fn waterfall_version(implementation: &str) -> Option<String> {
+	let version = implementation.split_once("Waterfall-Bootstrap:")?.1;
+	Some(version.split_once("-R")?.0.to_string())
+}
+
+fn parse_script(script: &str) -> Option<LaunchScript> {
+	for line in script.lines() {
+		let line = line.trim();
+		if line.starts_with('#') || line.starts_with("::") || line.to_ascii_lowercase().starts_with("rem ") {
+			continue;
+		}
+		let tokens: Vec<String> = line.split_whitespace().map(|it| it.trim_matches(['"', '\'']).to_string()).collect();
+		let Some(java) = tokens.iter().position(|it| is_java(it)) else {
+			continue;
+		};
+		let mut launch = LaunchScript::default();
+		if Path::new(&tokens[java]).is_absolute() {
+			launch.java = Some(tokens[java].clone());
+		}
+		let mut rest = tokens[java + 1..].iter();
+		while let Some(arg) = rest.next() {
+			if arg == "-jar" {
+				launch.server_file = rest.next().cloned();
+				break;
+			}
+			if arg.starts_with('@') && (arg.ends_with("unix_args.txt") || arg.ends_with("win_args.txt")) {
+				launch.launch_args.push(arg.clone());
+				break;
+			}
+			launch.jvm_args.push(arg.clone());
+		}
+		// arguments passed to script
+		launch.args = rest.filter(|it| !matches!(it.as_str(), "$@" | "$*" | "%*")).cloned().collect();
+		if launch.server_file.is_some() || !launch.launch_args.is_empty() {
+			return Some(launch);
+		}
+	}
+	None
+}
+
+fn is_java(token: &str) -> bool {
+	let name = token.rsplit(['/', '\\']).next().unwrap_or(token);
+	matches!(name, "java" | "java.exe" | "javaw" | "javaw.exe")
+}
+
+/// Arguments of `@file`, `#` starts comment
+fn parse_args_file(content: &str) -> Vec<String> {
+	content.lines()
+		.map(|it| it.split('#').next().unwrap_or_default())
+		.flat_map(|it| it.split_whitespace())
+		.map(|it| it.to_string())
+		.collect()
+}
+
+/// Size of `-Xmx` in megabytes
+fn parse_ram(value: &str) -> Option<u16> {
+	let (num, unit) = value.split_at(value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len()));
+	let num: u64 = num.parse().ok()?;
+	let mb = match unit.to_ascii_lowercase().as_str() {
+		"g" => { num * 1024 }
+		"m" => { num }
+		"k" => { num / 1024 }
+		"" => { num / 1024 / 1024 }
+		_ => { return None; }
+	};
+	Some(mb.min(u16::MAX as u64) as u16)
+}
+
+#[cfg(test)]
+mod test {
+	use crate::instance::mc_instance::ModType;
+	use crate::instance::server_import::{LaunchScript, paperclip_version, parse_ram, parse_script, waterfall_version};
+
+	#[test]
+	fn test_parse_script() {
+		let script = parse_script("#!/bin/sh\nexec /usr/lib/jvm/java-17/bin/java -Xms1G -Xmx4G -jar paper.jar --nogui \"$@\"\n").unwrap();
+		assert_eq!(script, LaunchScript {
+			java: Some("/usr/lib/jvm/java-17/bin/java".to_string()),
+			jvm_args: vec!["-Xms1G".to_string(), "-Xmx4G".to_string()],
+			server_file: Some("paper.jar".to_string()),
+			launch_args: vec![],
+			args: vec!["--nogui".to_string()],
+		});
+		let forge = parse_script("@echo off\nREM java -jar old.jar\njava @user_jvm_args.txt @libraries/net/minecraftforge/forge/1.20.1-47.2.0/win_args.txt nogui %*\npause\n").unwrap();
+		assert_eq!(forge.java, None);
+		assert_eq!(forge.jvm_args, vec!["@user_jvm_args.txt"]);
+		assert_eq!(forge.launch_args, vec!["@libraries/net/minecraftforge/forge/1.20.1-47.2.0/win_args.txt"]);
+		assert_eq!(forge.args, vec!["nogui"]);
+		assert!(parse_script("echo java is missing").is_none());
+	}
+
+	#[test]
+	fn test_versions() {
+		assert_eq!(parse_ram("4G"), Some(4096));
+		assert_eq!(parse_ram("2048m"), Some(2048));
+		assert_eq!(parse_ram("4X"), None);
+		assert!(matches!(paperclip_version("abc\tpurpur-1.20.4\tversions/1.20.4/purpur-1.20.4.jar"), Some((ModType::Purpur, ver)) if ver == "1.20.4"));
+		assert!(paperclip_version("abc\tvelocity-3.3.0\tvelocity.jar").is_none());
+		assert_eq!(waterfall_version("git:Waterfall-Bootstrap:1.20-R0.1-SNAPSHOT:abc1234:570").as_deref(), Some("1.20"));
+	}
+}
@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use tokio::fs::{create_dir_all, metadata, read_dir, remove_file, rename};
+use tokio::sync::{Mutex, OwnedRwLockReadGuard, RwLock};
+use tracing::{debug, warn};
+
+use crate::file_info::FileInfo;
+use crate::instance::mc_instance::McInstance;
+use crate::mc::mc_config::{MinecraftConfig, ZipEntry};
+
+/// One lock per archive, so clients asking for the same archive wait for a single build
+static BUILDING: Mutex<BTreeMap<PathBuf, Arc<Mutex<()>>>> = Mutex::const_new(BTreeMap::new());
+/// One lock per archive name of instance, it's held for reading until archive is opened by request
+/// and for writing while older archives are removed
+static SERVING: Mutex<BTreeMap<PathBuf, Arc<RwLock<()>>>> = Mutex::const_new(BTreeMap::new());
+
+#[derive(Debug, Clone, Copy)]
+pub enum ZipKind {
+	/// Files in `dist_folder`
+	Dist,
+	/// `config` folder
+	Config,
+}
+
+impl ZipKind {
+	pub fn name(self) -> &'static str {
+		match self {
+			ZipKind::Dist => { "dist" }
+			ZipKind::Config => { "config" }
+		}
+	}
+}
+
+/// Archive built from instance files, stored in `.zip_cache/<name>-<key>.zip`
+pub struct CachedZip {
+	pub path: PathBuf,
+	/// Hash of archived files, it's the same as long as content is unchanged
+	pub key: String,
+	/// Archive isn't removed while it's kept, keep it until file is opened
+	_guard: OwnedRwLockReadGuard<()>,
+}
+
+impl CachedZip {
+	/// Quoted key
+	pub fn etag(&self) -> String {
+		format!("\"{}\"", self.key)
+	}
+
+	/// File name client should save archive as
+	pub fn file_name(&self, instance: &str, name: &str) -> String {
+		format!("{instance}-{name}-{}.zip", self.key)
+	}
+}
+
+/// Get archive of instance files, it's only built when content is changed
+pub async fn get(instance: &McInstance, kind: ZipKind) -> Result<CachedZip> {
+	let files = match kind {
+		ZipKind::Dist => { instance.config.dist_files().await? }
+		ZipKind::Config => { instance.config.config_files().await? }
+	};
+	build(instance, kind.name(), files.into_iter().map(|(name, path)| (name, ZipEntry::File(path))).collect()).await
+}
+
+/// Get archive named `name` (same name replaces older archive), it's only built when entries are changed
+pub async fn build(instance: &McInstance, name: &str, entries: BTreeMap<String, ZipEntry>) -> Result<CachedZip> {
+	let mut sha = Sha256::default();
+	for (entry_name, entry) in &entries {
+		let hash = match entry {
+			ZipEntry::File(path) => { FileInfo::named(entry_name.clone(), path.clone()).await?.hash }
+			ZipEntry::Data(data) => { Sha256::digest(data).iter().map(|it| format!("{:02x}", it)).collect() }
+		};
+		sha.update(format!("{entry_name}\0{hash}\n"));
+	}
+	let key: String = sha.finalize()[..8].iter().map(|it| format!("{:02x}", it)).collect();
+	let folder = instance.dir(".zip_cache")?;
+	let path = folder.join(format!("{name}-{key}.zip"));
+	let serving = Arc::clone(SERVING.lock().await.entry(folder.join(name)).or_default());
+	{
+		let guard = Arc::clone(&serving).read_owned().await;
+		if metadata(&path).await.is_ok() {
+			return Ok(CachedZip { path, key, _guard: guard });
+		}
+	}
+
+	let lock = Arc::clone(BUILDING.lock().await.entry(path.clone()).or_default());
+	let _building = lock.lock().await;
+	let tmp = path.with_extension("zip.tmp");
+	// built by another request while waiting
+	if metadata(&path).await.is_err() {
+		debug!("building {name} zip of {} ({key})", instance.name);
+		create_dir_all(&folder).await?;
+		if let Err(err) = MinecraftConfig::zip_files(entries, tmp.clone()).await {
+			remove_file(&tmp).await.ok();
+			BUILDING.lock().await.remove(&path);
+			return Err(err);
+		}
+	}
+
+	// older archives aren't needed anymore, requests which already opened them keep their file handle
+	let guard = Arc::clone(&serving).write_owned().await;
+	if metadata(&tmp).await.is_ok() {
+		rename(&tmp, &path).await?;
+	}
+	BUILDING.lock().await.remove(&path);
+	let prefix = format!("{name}-");
+	let mut entries = read_dir(&folder).await?;
+	while let Some(entry) = entries.next_entry().await? {
+		let name = entry.file_name().to_string_lossy().to_string();
+		if name.starts_with(&prefix) && !name.ends_with(".tmp") && entry.path() != path {
+			if let Err(err) = remove_file(entry.path()).await {
+				warn!("Failed to remove old archive {name}: {err}");
+			}
+		}
+	}
+	Ok(CachedZip { path, key, _guard: guard.downgrade() })
+}
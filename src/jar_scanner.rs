@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Lines};
+use std::io::{BufRead, BufReader, Lines, Read, Seek};
 use std::ops::Deref;
 use std::path::Path;
 
@@ -15,6 +15,11 @@ pub async fn get_manifest(file: impl AsRef<Path>) -> Result<JarManifest> {
 	} else {
 		bail!("Failed to open zip");
 	};
+	read_manifest(&mut zip)
+}
+
+/// Read `META-INF/MANIFEST.MF` from opened jar
+pub fn read_manifest<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Result<JarManifest> {
 	let entry = zip.by_name("META-INF/MANIFEST.MF")?;
 	let reader = BufReader::new(entry);
 	JarManifest::new(reader.lines())
@@ -7,8 +7,8 @@ use anyhow::Result;
 use tracing::info;
 
 use crate::info::GlobalInfo;
-use crate::jar_scanner::get_manifest;
 use crate::manager::instance_manager::InstanceManager;
+use crate::manager::update_manager::UpdateManager;
 use crate::util::{config, logger};
 use crate::util::java::JavaManager;
 use crate::web::http;
@@ -45,7 +45,9 @@ fn main() -> Result<()> {
 		let mut manager = InstanceManager::new();
 		manager.init().await?;
 		let db = db::init().await?;
-		http::init(manager.into_extension(), db).await?;
+		let manager = manager.into_extension();
+		UpdateManager::start(manager.0.clone());
+		http::init(manager, db).await?;
 		Result::<()>::Ok(())
 	})
 }
\ No newline at end of file
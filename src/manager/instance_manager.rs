@@ -2,17 +2,21 @@ use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use axum::Extension;
 use dashmap::DashMap;
 use pedestal_rs::fs::path::normalize;
-use tokio::fs::{create_dir_all, File, read_dir, remove_dir};
+use tokio::fs::{create_dir, create_dir_all, File, metadata, read_dir, remove_dir, remove_file, symlink_metadata};
 use tokio::sync::{OwnedRwLockWriteGuard, RwLock};
 use tracing::{debug, error, info};
 
-use crate::instance::mc_instance::{McInstance, ModType};
+use crate::instance::mc_instance::{ADOPTED_CONFIG, McInstance, ModType};
+use crate::instance::server_import::ImportMode;
+use crate::mc::modpack::Modpack;
+use crate::util::http::DownloadProgress;
+use crate::util::java::JavaInfo;
 
-type Instance = Arc<RwLock<McInstance>>;
+pub type Instance = Arc<RwLock<McInstance>>;
 pub type InstanceManagerExt = Extension<Arc<RwLock<InstanceManager>>>;
 
 pub struct InstanceManager {
@@ -75,12 +79,13 @@ impl InstanceManager {
 		info!("scanning {:?} for minecraft instances", self.folder);
 		let mut dir = read_dir(&self.folder).await?;
 		while let Ok(Some(e)) = dir.next_entry().await {
-			if let Ok(typ) = e.file_type().await {
-				if typ.is_dir() {
+			// follow symlink of imported server
+			if let Ok(meta) = metadata(e.path()).await {
+				if meta.is_dir() {
 					match McInstance::load(e.path()).await {
 						Ok(mut instance) => {
 							let name = instance.name.clone();
-							match instance.init().await {
+							match instance.init(&()).await {
 								Ok(_) => {
 									debug!("found {name:?} at \"instances{}\"", instance.config.directory.rsplit(&self.folder).next().unwrap());
 									self.instances.insert(name, Arc::new(RwLock::new(instance)));
@@ -100,22 +105,88 @@ impl InstanceManager {
 		Ok(())
 	}
 
-	pub async fn new_instance(&self, name: &str, version: &str, typ: ModType) -> Result<Instance> {
+	/// Create folder for new instance, it fails if folder already exists so two creations can't share it
+	pub async fn instance_dir(&self, name: &str) -> Result<PathBuf> {
 		let path = normalize(self.folder.as_ref(), name)?;
-		if !path.exists() {
-			create_dir_all(&path).await?;
+		if let Some(parent) = path.parent() {
+			create_dir_all(parent).await?;
 		}
-		let instance = McInstance::generate(&path, version, typ).await?;
+		create_dir(&path).await.with_context(|| format!("Failed to create {path:?}"))?;
+		Ok(path)
+	}
+
+	/// Generate and initialize new instance, it may take minutes to download java and server.
+	/// Manager is not borrowed while downloading so other request won't wait for it.
+	/// Instance folder is removed if it fails or job is cancelled
+	pub async fn new_instance(this: &RwLock<Self>, name: &str, version: &str, typ: ModType, progress: &dyn DownloadProgress) -> Result<Instance> {
+		let path = this.read().await.instance_dir(name).await?;
+		let rollback = Rollback::remove_dir(path.clone());
+		let mut instance = McInstance::generate(&path, version, typ, progress).await?;
+		instance.init(progress).await?;
+		rollback.disarm();
+		let name = instance.name.clone();
+		let instance = Arc::new(RwLock::new(instance));
+		this.read().await.instances.insert(name, Arc::clone(&instance));
+		Ok(instance)
+	}
+
+	/// Create instance from modpack, instance folder is removed if it fails or job is cancelled.
+	/// [Modpack::resolve] should be called before, so curseforge files are downloaded too
+	pub async fn import_modpack(this: &RwLock<Self>, name: &str, pack: &Modpack, archive: &Path, progress: &dyn DownloadProgress) -> Result<Instance> {
+		// fails if folder exists, so it's never removed by rollback of this import
+		let path = this.read().await.instance_dir(name).await?;
+		let rollback = Rollback::remove_dir(path.clone());
+		let mut instance = McInstance::generate(&path, &pack.mc_version, pack.mod_type.clone(), progress).await?;
+		let mods = pack.install(archive, &instance, progress).await?;
+		instance.record_mods(mods).await?;
+		instance.init(progress).await?;
+		rollback.disarm();
+		info!("imported {} as {}", pack.name, instance.name);
 		let name = instance.name.clone();
 		let instance = Arc::new(RwLock::new(instance));
-		self.instances.insert(name, Arc::clone(&instance));
+		this.read().await.instances.insert(name, Arc::clone(&instance));
 		Ok(instance)
 	}
 
+	/// Take over existing server folder detected by [McInstance::detect], folder is symlinked or moved into instance folder.
+	/// Nothing is downloaded except java runtime, folder is put back if it fails or job is cancelled
+	pub async fn import_server(this: &RwLock<Self>, name: &str, mut instance: McInstance, source: &Path, mode: ImportMode, progress: &dyn DownloadProgress) -> Result<Instance> {
+		let (folder, target) = {
+			let manager = this.read().await;
+			(PathBuf::from(&manager.folder), normalize(manager.folder.as_ref(), name)?)
+		};
+		if symlink_metadata(&target).await.is_ok() {
+			bail!("{target:?} already exists");
+		}
+		create_dir_all(&folder).await?;
+		mode.apply(source, &target).await?;
+		instance.name = name.to_string();
+		let rollback = {
+			let (source, target) = (source.to_path_buf(), target.clone());
+			let config = target.join(ADOPTED_CONFIG);
+			Rollback::new(move || {
+				std::fs::remove_file(config).ok();
+				if let Err(err) = mode.revert(&source, &target) {
+					error!("Failed to put back {source:?}: {err}");
+				}
+			})
+		};
+		instance.adopt(&target, progress).await?;
+		instance.init(progress).await?;
+		rollback.disarm();
+		info!("imported {source:?} as {name} ({} {})", instance.mod_type.loader_name(), instance.version());
+		let instance = Arc::new(RwLock::new(instance));
+		this.read().await.instances.insert(name.to_string(), Arc::clone(&instance));
+		Ok(instance)
+	}
+
+	/// Imported server which is symlinked is unlinked, its folder is kept
 	pub async fn remove_instance(&self, name: &str) -> Result<Option<Instance>> {
 		let path = normalize(self.folder.as_ref(), name)?;
 		if let Some((_, instance)) = self.instances.remove(name) {
-			if path.exists() {
+			if symlink_metadata(&path).await.is_ok_and(|it| it.is_symlink()) {
+				remove_file(&path).await?;
+			} else if path.exists() {
 				remove_dir(instance.read().await.dir("").unwrap()).await?;
 			}
 			Ok(Some(instance))
@@ -124,6 +195,27 @@ impl InstanceManager {
 		}
 	}
 
+	/// Name of instances that configured to use java runtime,
+	/// it read config from disk so it also work before instances are loaded (eg. from cli)
+	pub async fn java_users(&self, java: &JavaInfo) -> Result<Vec<String>> {
+		let mut users = vec![];
+		if metadata(&self.folder).await.is_err() {
+			return Ok(users);
+		}
+		let mut dir = read_dir(&self.folder).await?;
+		while let Some(e) = dir.next_entry().await? {
+			if !metadata(e.path()).await.is_ok_and(|it| it.is_dir()) {
+				continue;
+			}
+			if let Ok(Some(instance)) = McInstance::read_config(e.path()).await {
+				if instance.config.uses_java(java) {
+					users.push(instance.name);
+				}
+			}
+		}
+		Ok(users)
+	}
+
 	pub fn names(&self) -> Vec<String> {
 		self.instances.iter().map(|it| it.key().as_str().to_string()).collect()
 	}
@@ -190,4 +282,37 @@ impl InstanceManager {
 		});
 		res.flatten()
 	}
-}
\ No newline at end of file
+}
+
+/// Undo half-created instance when creation fails; it also runs when job is cancelled as its future is dropped,
+/// so undo is blocking
+struct Rollback<F: FnOnce()>(Option<F>);
+
+impl<F: FnOnce()> Rollback<F> {
+	fn new(undo: F) -> Self {
+		Self(Some(undo))
+	}
+
+	/// Creation is done, keep everything
+	fn disarm(mut self) {
+		self.0 = None;
+	}
+}
+
+impl Rollback<Box<dyn FnOnce() + Send>> {
+	fn remove_dir(path: PathBuf) -> Self {
+		Self::new(Box::new(move || {
+			if let Err(err) = std::fs::remove_dir_all(&path) {
+				error!("Failed to remove {path:?}: {err}");
+			}
+		}))
+	}
+}
+
+impl<F: FnOnce()> Drop for Rollback<F> {
+	fn drop(&mut self) {
+		if let Some(undo) = self.0.take() {
+			undo();
+		}
+	}
+}
@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::spawn;
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::AbortHandle;
+use tracing::{debug, error};
+
+use crate::util::http::DownloadProgress;
+
+pub type JobId = u64;
+
+/// Finished jobs kept for query
+static MAX_FINISHED_JOBS: usize = 64;
+/// Minimum interval between progress events of a job
+static EVENT_INTERVAL: Duration = Duration::from_millis(250);
+
+static JOBS: RwLock<BTreeMap<JobId, Arc<Job>>> = RwLock::const_new(BTreeMap::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static EVENTS: OnceLock<broadcast::Sender<JobInfo>> = OnceLock::new();
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+	Running,
+	Completed,
+	Failed,
+	Cancelled,
+}
+
+/// Snapshot of job, also sent to websocket subscribers
+#[derive(Serialize, Debug, Clone)]
+pub struct JobInfo {
+	pub id: JobId,
+	pub name: String,
+	/// What job is doing right now eg. `downloading java 17`
+	pub phase: String,
+	/// Downloaded bytes of current phase
+	pub bytes: u64,
+	/// None if it's unknown
+	pub total: Option<u64>,
+	pub state: JobState,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+	/// Data produced by job, see [Job::set_result]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub result: Option<Value>,
+}
+
+/// Long running operation, it's also a [DownloadProgress] so it can be passed down to download
+pub struct Job {
+	info: Mutex<JobInfo>,
+	last_event: Mutex<Option<Instant>>,
+	abort: Mutex<Option<AbortHandle>>,
+}
+
+impl Job {
+	pub fn info(&self) -> JobInfo {
+		self.info.lock().unwrap().clone()
+	}
+
+	pub fn is_running(&self) -> bool {
+		self.info.lock().unwrap().state == JobState::Running
+	}
+
+	/// Attach data for client to read after job is completed
+	pub fn set_result(&self, result: impl Serialize) {
+		match serde_json::to_value(result) {
+			Ok(result) => { self.update(true, |info| info.result = Some(result)); }
+			Err(err) => { error!("Failed to serialize result of job {}: {err}", self.info().id); }
+		}
+	}
+
+	fn update(&self, force: bool, f: impl FnOnce(&mut JobInfo)) {
+		let info = {
+			let mut info = self.info.lock().unwrap();
+			f(&mut info);
+			info.clone()
+		};
+		let mut last = self.last_event.lock().unwrap();
+		let now = Instant::now();
+		if force || last.map(|it| now.duration_since(it) >= EVENT_INTERVAL).unwrap_or(true) {
+			*last = Some(now);
+			// nobody is listening is fine
+			events().send(info).ok();
+		}
+	}
+
+	fn finish(&self, state: JobState, error: Option<String>) {
+		self.update(true, |info| {
+			// cancelled job may still finish before it's aborted
+			if info.state == JobState::Running {
+				info.state = state;
+				info.error = error;
+			}
+		});
+	}
+}
+
+impl DownloadProgress for Job {
+	fn on_progress(&self, downloaded: u64, total: Option<u64>) {
+		self.update(Some(downloaded) == total, |info| {
+			info.bytes = downloaded;
+			info.total = total;
+		});
+	}
+
+	fn set_phase(&self, phase: &str) {
+		self.update(true, |info| {
+			info.phase = phase.to_string();
+			info.bytes = 0;
+			info.total = None;
+		});
+	}
+}
+
+fn events() -> &'static broadcast::Sender<JobInfo> {
+	EVENTS.get_or_init(|| broadcast::channel(256).0)
+}
+
+pub struct JobManager;
+
+impl JobManager {
+	/// Run operation in background, `block` receive the job to report its progress.
+	/// None if job with the same name is still running, so the same operation isn't started twice
+	pub async fn spawn<F, Fut>(name: impl Into<String>, block: F) -> Option<Arc<Job>>
+		where F: FnOnce(Arc<Job>) -> Fut,
+		      Fut: Future<Output=anyhow::Result<()>> + Send + 'static {
+		let name = name.into();
+		// checked and inserted under the same lock
+		let mut jobs = JOBS.write().await;
+		if jobs.values().any(|it| it.is_running() && it.info().name == name) {
+			return None;
+		}
+		let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+		let job = Arc::new(Job {
+			info: Mutex::new(JobInfo {
+				id,
+				name: name.clone(),
+				phase: String::new(),
+				bytes: 0,
+				total: None,
+				state: JobState::Running,
+				error: None,
+				result: None,
+			}),
+			last_event: Mutex::new(None),
+			abort: Mutex::new(None),
+		});
+		Self::prune(&mut jobs);
+		jobs.insert(id, Arc::clone(&job));
+		drop(jobs);
+		debug!("starting job {id} {name:?}");
+		let fut = block(Arc::clone(&job));
+		let this = Arc::clone(&job);
+		let handle = spawn(async move {
+			match fut.await {
+				Ok(_) => {
+					this.finish(JobState::Completed, None);
+				}
+				Err(err) => {
+					error!("Job {id} {name:?} failed: {err:?}");
+					this.finish(JobState::Failed, Some(format!("{err:#}")));
+				}
+			}
+		});
+		*job.abort.lock().unwrap() = Some(handle.abort_handle());
+		events().send(job.info()).ok();
+		Some(job)
+	}
+
+	fn prune(jobs: &mut BTreeMap<JobId, Arc<Job>>) {
+		let finished: Vec<JobId> = jobs.iter()
+			.filter(|(_, job)| !job.is_running())
+			.map(|(id, _)| *id)
+			.collect();
+		if finished.len() > MAX_FINISHED_JOBS {
+			for id in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+				jobs.remove(id);
+			}
+		}
+	}
+
+	pub async fn list() -> Vec<JobInfo> {
+		JOBS.read().await.values().map(|it| it.info()).collect()
+	}
+
+	pub async fn get(id: JobId) -> Option<JobInfo> {
+		JOBS.read().await.get(&id).map(|it| it.info())
+	}
+
+	/// Running job that has this name, only a hint to fail early as [Self::spawn] checks it again
+	pub async fn find_running(name: &str) -> Option<JobInfo> {
+		JOBS.read().await
+			.values()
+			.map(|it| it.info())
+			.find(|it| it.state == JobState::Running && it.name == name)
+	}
+
+	/// return None if job is not found; false if it's already finished
+	pub async fn cancel(id: JobId) -> Option<bool> {
+		let job = JOBS.read().await.get(&id).cloned()?;
+		if !job.is_running() {
+			return Some(false);
+		}
+		if let Some(handle) = job.abort.lock().unwrap().take() {
+			handle.abort();
+		}
+		job.finish(JobState::Cancelled, None);
+		Some(true)
+	}
+
+	/// Receive every job change
+	pub fn subscribe() -> broadcast::Receiver<JobInfo> {
+		events().subscribe()
+	}
+}
@@ -1 +1,3 @@
-pub mod instance_manager;
\ No newline at end of file
+pub mod instance_manager;
+pub mod job_manager;
+pub mod update_manager;
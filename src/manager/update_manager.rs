@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use tokio::spawn;
+use tokio::sync::RwLock;
+use tokio::time::sleep;
+use tracing::{debug, error, info, warn};
+
+use crate::instance::mc_mod::{MinecraftMod, ModSource};
+use crate::manager::instance_manager::{Instance, InstanceManager};
+use crate::mc::curseforge::CurseForgeTarget;
+use crate::mc::modrinth::ModrinthTarget;
+use crate::mc::{curseforge, modrinth};
+use crate::util::config::get_config;
+use crate::util::http::{DownloadProgress, new_client};
+
+/// Last check result of each instance
+static UPDATES: RwLock<BTreeMap<String, Vec<ModUpdate>>> = RwLock::const_new(BTreeMap::new());
+
+/// Newer compatible version of installed mod
+#[derive(Serialize, Debug, Clone)]
+pub struct ModUpdate {
+	/// Installed file
+	pub file_name: String,
+	pub name: String,
+	pub current_version: String,
+	/// Version name of update
+	pub version: String,
+	pub new_file_name: String,
+	/// Markdown from modrinth or html from curseforge
+	pub changelog: String,
+	pub source: ModSource,
+}
+
+pub struct UpdateManager;
+
+impl UpdateManager {
+	/// Check every instance periodically (`mod_updates.check_interval`)
+	pub fn start(manager: Arc<RwLock<InstanceManager>>) {
+		spawn(async move {
+			// don't slow down startup
+			sleep(Duration::from_secs(60)).await;
+			loop {
+				let interval = get_config().await.mod_updates.check_interval;
+				if interval == 0 {
+					sleep(Duration::from_secs(60 * 60)).await;
+					continue;
+				}
+				let instances: Vec<Instance> = manager.read().await.instances.iter().map(|it| Arc::clone(it.value())).collect();
+				for instance in instances {
+					match Self::check(&instance).await {
+						Ok(updates) if !updates.is_empty() => {
+							info!("{} mod updates found for {}", updates.len(), instance.read().await.name);
+						}
+						Ok(_) => {}
+						Err(err) => {
+							error!("Failed to check mod updates: {err}");
+						}
+					}
+				}
+				sleep(Duration::from_secs(interval * 60 * 60)).await;
+			}
+		});
+	}
+
+	/// Result of last check, None if instance hasn't been checked
+	pub async fn cached(name: &str) -> Option<Vec<ModUpdate>> {
+		UPDATES.read().await.get(name).cloned()
+	}
+
+	/// Find newer version of every mod installed from modrinth or curseforge and cache result
+	pub async fn check(instance: &Instance) -> Result<Vec<ModUpdate>> {
+		let (name, modrinth_target, curseforge_target, mods) = {
+			let instance = instance.read().await;
+			let mut mods = vec![];
+			for m in &instance.mods {
+				if m.source.is_some() && instance.find_mod(&m.file_name).await?.is_some() {
+					mods.push(m.clone());
+				}
+			}
+			(
+				instance.name.clone(),
+				ModrinthTarget::new(&instance.mod_type, instance.version()),
+				CurseForgeTarget::new(&instance.mod_type, instance.version()),
+				mods,
+			)
+		};
+		let client = new_client()?;
+		let curseforge_enabled = curseforge::configured().await;
+		let mut updates = vec![];
+		for m in mods {
+			let update = match &m.source {
+				Some(ModSource::Modrinth { project_id, version_id }) => {
+					// compared by publish date, installed version can be newer than latest compatible one
+					let latest = match modrinth::newer(&client, project_id, version_id, &modrinth_target).await {
+						Ok(Some(it)) => { it }
+						Ok(None) => { continue; }
+						Err(err) => {
+							warn!("Failed to check update of {}: {err}", m.file_name);
+							continue;
+						}
+					};
+					ModUpdate {
+						file_name: m.file_name.clone(),
+						name: m.name.clone(),
+						current_version: m.version.clone(),
+						version: latest.version_number.clone(),
+						new_file_name: latest.primary_file().map(|it| it.filename.clone()).unwrap_or_default(),
+						changelog: latest.changelog.clone().unwrap_or_default(),
+						source: ModSource::Modrinth { project_id: project_id.clone(), version_id: latest.id },
+					}
+				}
+				Some(ModSource::CurseForge { project_id, file_id }) if curseforge_enabled => {
+					let latest = match curseforge::latest(&client, *project_id, &curseforge_target).await {
+						Ok(it) => { it }
+						Err(err) => {
+							warn!("Failed to check update of {}: {err}", m.file_name);
+							continue;
+						}
+					};
+					// file id grows over time
+					if latest.id <= *file_id {
+						continue;
+					}
+					ModUpdate {
+						file_name: m.file_name.clone(),
+						name: m.name.clone(),
+						current_version: m.version.clone(),
+						version: latest.display_name.clone(),
+						new_file_name: latest.file_name.clone(),
+						changelog: curseforge::changelog(&client, *project_id, latest.id).await.unwrap_or_default(),
+						source: ModSource::CurseForge { project_id: *project_id, file_id: latest.id },
+					}
+				}
+				_ => { continue; }
+			};
+			updates.push(update);
+		}
+		debug!("{} has {} mod updates", name, updates.len());
+		UPDATES.write().await.insert(name, updates.clone());
+		Ok(updates)
+	}
+
+	/// Replace installed files with their update (every update if `files` is empty).
+	/// Old files are moved to instance mod history, they are restored if any download fails
+	pub async fn apply(instance: &Instance, files: &[String], progress: &dyn DownloadProgress) -> Result<Vec<MinecraftMod>> {
+		let name = instance.read().await.name.clone();
+		let updates = match Self::cached(&name).await {
+			Some(it) => { it }
+			None => { Self::check(instance).await? }
+		};
+		let selected: Vec<&ModUpdate> = updates.iter().filter(|it| files.is_empty() || files.contains(&it.file_name)).collect();
+		if selected.is_empty() {
+			return Ok(vec![]);
+		}
+		let (before, mod_dir, mut history) = {
+			let instance = instance.read().await;
+			(instance.mods.clone(), instance.mod_dir()?, instance.mod_history().await?)
+		};
+		let after: Vec<MinecraftMod> = before.iter()
+			.map(|m| match selected.iter().find(|it| it.file_name == m.file_name) {
+				Some(update) => {
+					MinecraftMod {
+						file_name: update.new_file_name.clone(),
+						version: update.version.clone(),
+						source: Some(update.source.clone()),
+						..m.clone()
+					}
+				}
+				None => { m.clone() }
+			})
+			.collect();
+		let diff = MinecraftMod::diff_mod(&before, &after);
+
+		let mut archived = vec![];
+		for old in &diff.to_remove {
+			let Some((path, _)) = instance.read().await.find_mod(&old.file_name).await? else {
+				continue;
+			};
+			let replaced_by = after.iter()
+				.find(|it| matches!((&it.source, &old.source), (Some(a), Some(b)) if a.same_project(b)))
+				.map(|it| it.file_name.clone());
+			progress.set_phase(&format!("archiving {}", old.file_name));
+			archived.push(history.archive(&path, MinecraftMod::clone(old), replaced_by).await?);
+		}
+
+		let client = new_client()?;
+		let mut installed = vec![];
+		let mut result = Ok(());
+		for new in &diff.to_add {
+			let res = match &new.source {
+				Some(ModSource::Modrinth { version_id, .. }) => {
+					match modrinth::version(&client, version_id).await {
+						Ok(version) => { modrinth::install(&client, &[version], &mod_dir, progress).await }
+						Err(err) => { Err(err.into()) }
+					}
+				}
+				Some(ModSource::CurseForge { project_id, file_id }) => {
+					match curseforge::file(&client, *project_id, *file_id).await {
+						Ok(file) if file.download_url.is_none() => {
+							Err(anyhow::anyhow!("{} has to be downloaded manually", file.file_name))
+						}
+						Ok(file) => { curseforge::install(&client, &[file], &mod_dir, progress).await }
+						Err(err) => { Err(err.into()) }
+					}
+				}
+				None => { continue; }
+			};
+			match res {
+				Ok(mods) => { installed.extend(mods) }
+				Err(err) => {
+					result = Err(err);
+					break;
+				}
+			}
+		}
+
+		let mut instance = instance.write().await;
+		if let Err(err) = result {
+			warn!("Failed to update mods of {name}, rolling back: {err}");
+			for id in archived {
+				instance.rollback_mod(id).await?;
+			}
+			bail!(err);
+		}
+		instance.record_mods(installed.clone()).await?;
+		// only after whole batch is installed, otherwise rollback above could miss pruned files
+		history.prune(get_config().await.mod_updates.history_size).await?;
+		if let Some(cached) = UPDATES.write().await.get_mut(&name) {
+			cached.retain(|it| !selected.iter().any(|applied| applied.file_name == it.file_name));
+		}
+		Ok(installed)
+	}
+}
@@ -0,0 +1,319 @@
+use std::io;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use anyhow::Result;
+use hashbrown::HashSet;
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use tracing::debug;
+
+use crate::instance::mc_instance::ModType;
+use crate::instance::mc_mod::{MinecraftMod, ModSource};
+use crate::util::artifact_cache;
+use crate::util::config::get_config;
+use crate::util::errors::reqwest_to_io;
+use crate::util::http::{DownloadProgress, FileHash};
+
+static MINECRAFT_GAME_ID: u32 = 432;
+static CLASS_MODS: u32 = 6;
+static CLASS_BUKKIT_PLUGINS: u32 = 5;
+/// `relationType` of required dependency
+static RELATION_REQUIRED: u32 = 3;
+/// `algo` of sha1 in file hashes, the other one is md5
+static HASH_SHA1: u32 = 1;
+/// Max page size of curseforge api
+static FILES_PAGE_SIZE: usize = 50;
+
+/// True if api key is set, every request will be rejected without it
+pub async fn configured() -> bool {
+	!get_config().await.curseforge.api_key.is_empty()
+}
+
+async fn get(client: &Client, path: &str) -> RequestBuilder {
+	let config = get_config().await;
+	client.get(format!("{}/v1{path}", config.curseforge.api.trim_end_matches('/')))
+		.header("x-api-key", config.curseforge.api_key.as_str())
+}
+
+/// Every response is wrapped in `data`
+#[derive(Deserialize)]
+struct Data<T> {
+	data: T,
+}
+
+async fn get_data<T: DeserializeOwned>(client: &Client, path: &str, query: &[(&str, String)]) -> io::Result<T> {
+	let resp = get(client, path).await
+		.query(query)
+		.send().await.map_err(reqwest_to_io)?;
+	if resp.status().as_u16() == 404 {
+		return Err(io::Error::new(ErrorKind::NotFound, format!("{path} was not found on curseforge")));
+	}
+	let data: Data<T> = resp.error_for_status().map_err(reqwest_to_io)?.json().await.map_err(reqwest_to_io)?;
+	Ok(data.data)
+}
+
+/// Project class and loader of server
+#[derive(Debug, Clone)]
+pub struct CurseForgeTarget {
+	pub class_id: u32,
+	/// `modLoaderType`; None for plugins
+	pub loader: Option<u32>,
+	/// None if game version doesn't matter (proxy)
+	pub game_version: Option<String>,
+}
+
+impl CurseForgeTarget {
+	pub fn new(mod_type: &ModType, mc_version: &str) -> Self {
+		let (class_id, loader) = match mod_type {
+			ModType::Vanilla => { (CLASS_MODS, None) }
+			ModType::Forge(_) => { (CLASS_MODS, Some(1)) }
+			ModType::NeoForge(_) => { (CLASS_MODS, Some(6)) }
+			_ => { (CLASS_BUKKIT_PLUGINS, None) }
+		};
+		Self {
+			class_id,
+			loader,
+			game_version: (!mod_type.is_proxy()).then(|| mc_version.to_string()),
+		}
+	}
+
+	fn query(&self) -> Vec<(&'static str, String)> {
+		let mut query = vec![];
+		if let Some(loader) = self.loader {
+			query.push(("modLoaderType", loader.to_string()));
+		}
+		if let Some(version) = &self.game_version {
+			query.push(("gameVersion", version.clone()));
+		}
+		query
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeMod {
+	pub id: u64,
+	pub name: String,
+	pub slug: String,
+	#[serde(default)]
+	pub summary: String,
+	#[serde(default)]
+	pub download_count: f64,
+	#[serde(default)]
+	pub links: CurseForgeLinks,
+	/// False if author disabled third party download
+	#[serde(default)]
+	pub allow_mod_distribution: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeLinks {
+	#[serde(default)]
+	pub website_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+	pub data: Vec<CurseForgeMod>,
+	pub pagination: Pagination,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Pagination {
+	pub index: u32,
+	pub page_size: u32,
+	pub result_count: u32,
+	pub total_count: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeFile {
+	pub id: u64,
+	pub mod_id: u64,
+	pub display_name: String,
+	pub file_name: String,
+	/// 1: release, 2: beta, 3: alpha
+	pub release_type: u32,
+	#[serde(default)]
+	pub file_length: u64,
+	/// None if distribution is disabled by author
+	#[serde(default)]
+	pub download_url: Option<String>,
+	#[serde(default)]
+	pub hashes: Vec<CurseForgeHash>,
+	#[serde(default)]
+	pub game_versions: Vec<String>,
+	#[serde(default)]
+	pub dependencies: Vec<CurseForgeDependency>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CurseForgeHash {
+	pub value: String,
+	pub algo: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeDependency {
+	pub mod_id: u64,
+	pub relation_type: u32,
+}
+
+impl CurseForgeFile {
+	pub fn hash(&self) -> Option<FileHash> {
+		self.hashes.iter().find(|it| it.algo == HASH_SHA1).map(|it| FileHash::Sha1(it.value.clone()))
+	}
+}
+
+/// File which can't be downloaded by api, user has to download it from website and upload it
+#[derive(Serialize, Debug, Clone)]
+pub struct ManualDownload {
+	pub project_id: u64,
+	pub file_id: u64,
+	pub file_name: String,
+	/// Page to download file from
+	pub url: String,
+}
+
+/// Result of [resolve]
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Resolved {
+	pub files: Vec<CurseForgeFile>,
+	pub manual: Vec<ManualDownload>,
+}
+
+pub async fn search(client: &Client, query: &str, target: &CurseForgeTarget, index: u32, page_size: u32) -> io::Result<SearchResult> {
+	let mut params = target.query();
+	params.extend([
+		("gameId", MINECRAFT_GAME_ID.to_string()),
+		("classId", target.class_id.to_string()),
+		("searchFilter", query.to_string()),
+		("index", index.to_string()),
+		("pageSize", page_size.to_string()),
+	]);
+	let resp = get(client, "/mods/search").await
+		.query(&params)
+		.send().await.map_err(reqwest_to_io)?
+		.error_for_status().map_err(reqwest_to_io)?;
+	resp.json().await.map_err(reqwest_to_io)
+}
+
+pub async fn project(client: &Client, id: u64) -> io::Result<CurseForgeMod> {
+	get_data(client, &format!("/mods/{id}"), &[]).await
+}
+
+#[derive(Deserialize)]
+struct FilesPage {
+	data: Vec<CurseForgeFile>,
+	pagination: Pagination,
+}
+
+/// Files of project compatible with target, newest first. Every page is fetched, api doesn't sort them
+pub async fn files(client: &Client, id: u64, target: &CurseForgeTarget) -> io::Result<Vec<CurseForgeFile>> {
+	let mut files: Vec<CurseForgeFile> = vec![];
+	loop {
+		let mut params = target.query();
+		params.extend([("index", files.len().to_string()), ("pageSize", FILES_PAGE_SIZE.to_string())]);
+		let resp = get(client, &format!("/mods/{id}/files")).await
+			.query(&params)
+			.send().await.map_err(reqwest_to_io)?;
+		if resp.status().as_u16() == 404 {
+			return Err(io::Error::new(ErrorKind::NotFound, format!("CurseForge project {id} was not found")));
+		}
+		let page: FilesPage = resp.error_for_status().map_err(reqwest_to_io)?.json().await.map_err(reqwest_to_io)?;
+		let done = page.data.is_empty() || files.len() + page.data.len() >= page.pagination.total_count as usize;
+		files.extend(page.data);
+		if done {
+			break;
+		}
+	}
+	files.sort_by(|a, b| b.id.cmp(&a.id));
+	Ok(files)
+}
+
+pub async fn file(client: &Client, id: u64, file_id: u64) -> io::Result<CurseForgeFile> {
+	get_data(client, &format!("/mods/{id}/files/{file_id}"), &[]).await
+}
+
+/// Changelog of file in html
+pub async fn changelog(client: &Client, id: u64, file_id: u64) -> io::Result<String> {
+	get_data(client, &format!("/mods/{id}/files/{file_id}/changelog"), &[]).await
+}
+
+pub async fn latest(client: &Client, id: u64, target: &CurseForgeTarget) -> io::Result<CurseForgeFile> {
+	files(client, id, target).await?
+		.into_iter()
+		.next()
+		.ok_or_else(|| io::Error::new(ErrorKind::NotFound, format!("CurseForge project {id} has no file for this server")))
+}
+
+/// Pick file (latest compatible one if not specified) and its required dependencies.
+/// Projects in `installed` are skipped; files that can't be downloaded by api are listed in [Resolved::manual]
+pub async fn resolve(client: &Client,
+                     id: u64,
+                     file_id: Option<u64>,
+                     target: &CurseForgeTarget,
+                     installed: &HashSet<u64>) -> io::Result<Resolved> {
+	let first = match file_id {
+		Some(file_id) => { file(client, id, file_id).await? }
+		None => { latest(client, id, target).await? }
+	};
+	let mut seen = installed.clone();
+	seen.insert(first.mod_id);
+	let mut queue = vec![first];
+	let mut resolved = Resolved::default();
+	while let Some(current) = queue.pop() {
+		for dep in current.dependencies.iter().filter(|it| it.relation_type == RELATION_REQUIRED) {
+			if seen.insert(dep.mod_id) {
+				debug!("{} requires curseforge project {}", current.display_name, dep.mod_id);
+				queue.push(latest(client, dep.mod_id, target).await?);
+			}
+		}
+		if current.download_url.is_some() {
+			resolved.files.push(current);
+		} else {
+			resolved.manual.push(manual_download(client, &current).await?);
+		}
+	}
+	Ok(resolved)
+}
+
+/// Page to download file which distribution is disabled
+pub async fn manual_download(client: &Client, file: &CurseForgeFile) -> io::Result<ManualDownload> {
+	let website = project(client, file.mod_id).await?.links.website_url.unwrap_or_default();
+	Ok(ManualDownload {
+		project_id: file.mod_id,
+		file_id: file.id,
+		url: format!("{}/files/{}", website.trim_end_matches('/'), file.id),
+		file_name: file.file_name.clone(),
+	})
+}
+
+/// Download files into folder, return parsed mods linked to their curseforge file
+pub async fn install(client: &Client, files: &[CurseForgeFile], folder: &Path, progress: &dyn DownloadProgress) -> Result<Vec<MinecraftMod>> {
+	let mut installed = vec![];
+	for file in files {
+		let Some(url) = &file.download_url else {
+			continue;
+		};
+		// don't trust file name from remote
+		let file_name = Path::new(&file.file_name).file_name()
+			.ok_or_else(|| io::Error::new(ErrorKind::InvalidData, format!("Invalid file name {}", file.file_name)))?;
+		progress.set_phase(&format!("downloading {}", file.file_name));
+		let path = artifact_cache::fetch(client, url, folder.join(file_name), file.hash().as_ref(), progress).await?;
+		let mut mc_mod = MinecraftMod::try_parse(&path).await?;
+		mc_mod.source = Some(ModSource::CurseForge {
+			project_id: file.mod_id,
+			file_id: file.id,
+		});
+		installed.push(mc_mod);
+	}
+	Ok(installed)
+}
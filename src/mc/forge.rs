@@ -0,0 +1,132 @@
+use std::borrow::Cow;
+use std::path::Path;
+use std::process::Stdio;
+
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
+use tokio::fs::{metadata, read_dir, remove_file};
+use tokio::process::Command;
+use tracing::{debug, error, info, warn};
+
+use crate::mc::mc_config::MinecraftConfig;
+use crate::util::artifact_cache;
+use crate::util::http::{DownloadProgress, FileHash, parse_checksum_file};
+
+#[cfg(not(target_os = "windows"))]
+static ARGS_FILE: &str = "unix_args.txt";
+#[cfg(target_os = "windows")]
+static ARGS_FILE: &str = "win_args.txt";
+
+/// Maven artifact of forge-like loader
+pub struct ForgeArtifact<'a> {
+	/// Maven repository root
+	pub repo: &'static str,
+	/// Group path eg. `net/minecraftforge`
+	pub group: &'static str,
+	pub name: &'static str,
+	/// Full artifact version eg. `1.20.1-47.2.0` for forge or `20.4.80-beta` for neoforge
+	pub version: Cow<'a, str>,
+}
+
+impl ForgeArtifact<'_> {
+	pub fn installer_url(&self) -> String {
+		let ForgeArtifact { repo, group, name, version } = self;
+		format!("{repo}/{group}/{name}/{version}/{name}-{version}-installer.jar")
+	}
+
+	/// Sha1 published next to installer, None if it can't be fetched
+	pub async fn installer_hash(&self, client: &Client) -> Option<FileHash> {
+		let url = artifact_cache::rewrite(&format!("{}.sha1", self.installer_url())).await;
+		let resp = client.get(url).send().await.and_then(|it| it.error_for_status());
+		match resp {
+			Ok(resp) => {
+				let content = resp.text().await.ok()?;
+				parse_checksum_file(&content).map(|it| FileHash::Sha1(it.to_string()))
+			}
+			Err(err) => {
+				warn!("Failed to get checksum of {}: {err}", self.installer_url());
+				None
+			}
+		}
+	}
+
+	/// Arguments file generated by installer (1.17+)
+	fn args_file(&self) -> String {
+		let ForgeArtifact { group, name, version, .. } = self;
+		format!("libraries/{group}/{name}/{version}/{ARGS_FILE}")
+	}
+}
+
+/// How server should be launched after installation
+#[derive(Debug)]
+pub enum ForgeLaunch {
+	/// Launch using `@libraries/.../unix_args.txt`
+	ArgsFile(String),
+	/// Legacy installation that produce runnable jar
+	Jar(String),
+}
+
+impl ForgeLaunch {
+	pub fn apply(self, config: &mut MinecraftConfig) {
+		match self {
+			ForgeLaunch::ArgsFile(file) => {
+				config.launch_args = vec![format!("@{file}")];
+			}
+			ForgeLaunch::Jar(jar) => {
+				config.server_file = jar;
+				config.launch_args.clear();
+			}
+		}
+	}
+}
+
+/// Download installer and run `--installServer` inside instance directory using java from config
+pub async fn install(client: &Client, artifact: &ForgeArtifact<'_>, config: &MinecraftConfig, progress: &dyn DownloadProgress) -> Result<ForgeLaunch> {
+	let dir = config.dir("")?;
+	let installer_name = format!("{}-{}-installer.jar", artifact.name, artifact.version);
+	let installer = dir.join(&installer_name);
+	progress.set_phase(&format!("downloading {installer_name}"));
+	let expected = artifact.installer_hash(client).await;
+	artifact_cache::fetch(client, &artifact.installer_url(), &installer, expected.as_ref(), progress).await?;
+
+	let java = if config.java.is_empty() { "java" } else { config.java.as_str() };
+	info!("installing {} {} to {dir:?}", artifact.name, artifact.version);
+	progress.set_phase(&format!("installing {} {}", artifact.name, artifact.version));
+	let output = Command::new(java)
+		.arg("-jar")
+		.arg(&installer_name)
+		.arg("--installServer")
+		.current_dir(config.canonicalized("")?)
+		.kill_on_drop(true)
+		.stdin(Stdio::null())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.output()
+		.await
+		.context("Run installer")?;
+	remove_file(&installer).await.ok();
+	if !output.status.success() {
+		error!("installer exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr));
+		bail!("Failed to install {} {}", artifact.name, artifact.version);
+	}
+	debug!("installer finished");
+	detect_launch(&dir, artifact).await
+}
+
+/// Find out how installed server should be launched
+pub async fn detect_launch(dir: &Path, artifact: &ForgeArtifact<'_>) -> Result<ForgeLaunch> {
+	let args_file = artifact.args_file();
+	if metadata(dir.join(&args_file)).await.is_ok() {
+		return Ok(ForgeLaunch::ArgsFile(args_file));
+	}
+	// older version put `forge-<version>.jar` (or `-universal.jar`) next to installer
+	let prefix = format!("{}-{}", artifact.name, artifact.version);
+	let mut files = read_dir(dir).await?;
+	while let Some(ent) = files.next_entry().await? {
+		let name = ent.file_name().to_string_lossy().to_string();
+		if name.starts_with(&prefix) && name.ends_with(".jar") && !name.contains("installer") {
+			return Ok(ForgeLaunch::Jar(name));
+		}
+	}
+	bail!("Can't find launch arguments after installing {prefix}")
+}
@@ -1,22 +1,25 @@
-use std::io::{Cursor, Seek, Write};
+use std::collections::BTreeMap;
+use std::io;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 
 use anyhow::{anyhow, bail, Context, Result};
 use futures::StreamExt;
 use pedestal_rs::fs::path;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use tokio::fs::{File, metadata, read_dir};
-use tokio::io::AsyncReadExt;
 use tokio::process::{Child, Command};
 use tokio::task::spawn_blocking;
 use tracing::debug;
 use zip::{CompressionMethod, ZipWriter};
-use zip::result::ZipResult;
 use zip::write::FileOptions;
 
 use crate::file_scanner::scan_recursive;
-use crate::util::java::JavaManager;
+use crate::instance::mc_mod::DISABLED_SUFFIX;
+use crate::util::glob::glob_match;
+use crate::util::java::JavaInfo;
 
 static DEFAULT_JVM_ARGS: &str = include_str!("../resources/default_jvm_args.txt");
 
@@ -27,12 +30,43 @@ pub struct MinecraftConfig {
 	pub max_ram: u16,
 	pub jvm_args: Vec<String>,
 	pub server_file: String,
+	/// Arguments used instead of `-jar server_file` (eg. `@libraries/.../unix_args.txt` from forge installer)
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub launch_args: Vec<String>,
 	pub args: Vec<String>,
 	#[serde(skip)]
 	pub directory: String,
-	//pub(crate) exclude: String,
-	//#[serde(skip_serializing_if = "Vec::is_empty")]
 	pub(crate) dist_folder: Vec<String>,
+	/// Globs of files (relative to instance) inside `dist_folder` to send to client, everything if empty
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub include: Vec<String>,
+	/// Globs of files that stay on server even if they're included, eg. `mods/spark-*.jar`
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub exclude: Vec<String>,
+	/// Regex of file name that stay on server, eg. `.+\.(bak|old)$`
+	#[serde(default, skip_serializing_if = "String::is_empty")]
+	pub exclude_regex: String,
+	/// Folder of client-only files, it's merged into distributed files as if it was instance folder
+	/// (`client/mods/minimap.jar` is sent as `mods/minimap.jar`) and it's never loaded by server
+	#[serde(default = "default_client_folder")]
+	pub client_folder: String,
+	/// Refuse to start server when mods have errors (missing dependency, wrong loader etc.)
+	#[serde(default = "default_check_mods")]
+	pub check_mods: bool,
+}
+
+fn default_client_folder() -> String {
+	String::from("client")
+}
+
+const fn default_check_mods() -> bool { true }
+
+/// Content of zip entry
+#[derive(Debug, Clone)]
+pub enum ZipEntry {
+	File(PathBuf),
+	/// Generated content
+	Data(Vec<u8>),
 }
 
 
@@ -46,10 +80,15 @@ impl Default for MinecraftConfig {
 				.map(|it| it.to_string())
 				.collect::<Vec<String>>(),
 			server_file: "server.jar".to_string(),
+			launch_args: Vec::new(),
 			args: vec!["nogui".to_string()],
 			directory: String::new(),
 			dist_folder: vec![String::from("mods")],
-			//exclude: String::from(".+\\.(bak|old)$"),
+			include: Vec::new(),
+			exclude: Vec::new(),
+			exclude_regex: String::new(),
+			client_folder: default_client_folder(),
+			check_mods: default_check_mods(),
 		}
 	}
 }
@@ -65,13 +104,26 @@ impl MinecraftConfig {
 		Ok(path::normalize(&path.canonicalize()?, name).context("Path normalization")?)
 	}
 
-	pub async fn use_java(&mut self, java_id: &str) -> bool {
-		let java = JavaManager::get_by_id(java_id).await;
-		if let Some(java) = java {
-			self.java = java.path_for(&self.directory).to_string_lossy().to_string();
-			true
-		} else {
-			false
+	/// Switch to java runtime, graalvm specific arguments will be added or removed to match it
+	pub fn use_java(&mut self, java: &JavaInfo) {
+		self.java = java.path_for(&self.directory).to_string_lossy().to_string();
+		self.jvm_args.retain(|it| !JavaInfo::is_performance_arg(it));
+		self.jvm_args.extend(java.performance_args().iter().map(|it| it.to_string()));
+	}
+
+	/// Check if this config is using java runtime
+	pub fn uses_java(&self, java: &JavaInfo) -> bool {
+		if self.java.is_empty() {
+			return false;
+		}
+		if !self.java.contains('/') || !java.path().contains('/') {
+			// executable from $PATH
+			return self.java == java.path();
+		}
+		let used = Path::new(&self.directory).join(&self.java);
+		match (used.canonicalize(), Path::new(java.path()).canonicalize()) {
+			(Ok(used), Ok(java)) => { used == java }
+			_ => { false }
 		}
 	}
 
@@ -133,77 +185,87 @@ impl MinecraftConfig {
 		Ok(())
 	}*/
 
-	async fn zip_folder<W: Write + Seek + Send + 'static>(parent: &Path, folder: &Path, mut out_zip: ZipWriter<W>) -> Result<ZipWriter<W>> {
-		use futures::future;
-		let configs = scan_recursive(folder);
-		let files: Vec<PathBuf> = configs
-			.then(|ent| { future::ready(ent.ok().map(|it| it.path())) })
-			.filter(|it| future::ready(it.is_some()))
-			.map(|it| it.unwrap())
-			.collect().await; // too complex to read if I continue to use stream
-		let option = FileOptions::default()
-			.compression_method(CompressionMethod::Deflated)
-			.large_file(false)
-			.unix_permissions(0o755);
-		let mut buffer = vec![0u8; 8192];
-		for file in files {
-			let f = file.strip_prefix(&parent).context("strip prefix")?.to_path_buf();
-			out_zip = spawn_blocking(move || {
-				out_zip.start_file(f.to_string_lossy(), option)?;
-				Result::<_>::Ok(out_zip)
-			}).await??;
+	/// Files in `dist_folder` which client should have by name it's sent as, disabled mods are skipped
+	pub async fn dist_files(&self) -> Result<BTreeMap<String, PathBuf>> {
+		self.distributed_files(&self.dist_folder).await
+	}
 
-			let mut file_content = File::open(file).await?;
-			loop {
-				let read = file_content.read(&mut buffer).await?;
-				match read {
-					0 => { break; }
-					n => {
-						let (zip, buf) = spawn_blocking(move || {
-							out_zip.write_all(&buffer[..n])?;
-							Result::<_>::Ok((out_zip, buffer))
-						}).await??;
-						out_zip = zip;
-						buffer = buf;
+	pub async fn config_files(&self) -> Result<BTreeMap<String, PathBuf>> {
+		self.distributed_files(&["config".to_string()]).await
+	}
+
+	/// Files inside folders that pass include/exclude rules, files from client folder replace server ones
+	async fn distributed_files(&self, folders: &[String]) -> Result<BTreeMap<String, PathBuf>> {
+		let exclude_regex = self.exclude_regex()?;
+		let parent = self.dir("")?;
+		let client = self.client_dir()?;
+		let mut files = BTreeMap::new();
+		for (base, is_client) in [(&parent, false), (&client, true)] {
+			for folder in folders {
+				let folder = path::normalize(base, folder).context("Path normalization")?;
+				if metadata(&folder).await.is_err() {
+					continue;
+				}
+				let mut entries = scan_recursive(folder);
+				while let Some(entry) = entries.next().await {
+					let path = entry?.path();
+					if !is_client && path.starts_with(&client) {
+						continue;
+					}
+					let name = relative_name(base, &path);
+					if self.is_distributed(&name, exclude_regex.as_ref()) {
+						files.insert(name, path);
 					}
 				}
 			}
 		}
-		Ok(out_zip)
+		Ok(files)
 	}
 
-	pub async fn zip_dist(&self) -> Result<Vec<u8>> {
-		let content_handler = Vec::<u8>::with_capacity(8192);
-		let mut out_zip = ZipWriter::new(Cursor::new(content_handler));
+	/// Folder of client-only files
+	pub fn client_dir(&self) -> Result<PathBuf> {
+		self.dir(&self.client_folder)
+	}
 
-		let parent = self.dir("")?;
-		for x in &self.dist_folder {
-			let path = self.dir(x)?;
-			if metadata(&path).await.is_ok() {
-				out_zip = Self::zip_folder(&parent, &path, out_zip).await?;
-			}
+	pub fn exclude_regex(&self) -> Result<Option<Regex>> {
+		if self.exclude_regex.is_empty() {
+			return Ok(None);
 		}
-		let res: ZipResult<Cursor<Vec<u8>>> = spawn_blocking(move || {
-			out_zip.finish()
-		}).await?;
-
-		Ok(res?.into_inner())
+		Ok(Some(Regex::new(&self.exclude_regex).context("Invalid exclude_regex")?))
 	}
 
-	pub async fn zip_config(&self) -> Result<Vec<u8>> {
-		let content_handler = Vec::<u8>::with_capacity(8192);
-		let mut out_zip = ZipWriter::new(Cursor::new(content_handler));
-
-		let parent = self.dir("")?;
-		let path = self.dir("config")?;
-		if metadata(&path).await.is_ok() {
-			out_zip = Self::zip_folder(&parent, &path, out_zip).await?;
-		}
-		let res: ZipResult<Cursor<Vec<u8>>> = spawn_blocking(move || {
-			out_zip.finish()
-		}).await?;
+	/// Check file name (relative to instance or client folder) against include/exclude rules
+	pub fn is_distributed(&self, name: &str, exclude_regex: Option<&Regex>) -> bool {
+		let file_name = name.rsplit('/').next().unwrap_or(name);
+		!name.ends_with(DISABLED_SUFFIX)
+			&& (self.include.is_empty() || self.include.iter().any(|it| glob_match(it, name)))
+			&& !self.exclude.iter().any(|it| glob_match(it, name))
+			&& !exclude_regex.is_some_and(|it| it.is_match(file_name))
+	}
 
-		Ok(res?.into_inner())
+	/// Write entries into zip at `out`, see [Self::dist_files]
+	pub async fn zip_files(entries: BTreeMap<String, ZipEntry>, out: PathBuf) -> Result<()> {
+		spawn_blocking(move || {
+			let mut zip = ZipWriter::new(std::fs::File::create(&out)?);
+			let option = FileOptions::default()
+				.compression_method(CompressionMethod::Deflated)
+				.unix_permissions(0o755);
+			for (name, entry) in entries {
+				match entry {
+					ZipEntry::File(file) => {
+						let mut content = std::fs::File::open(&file)?;
+						zip.start_file(name, option.large_file(content.metadata()?.len() >= u32::MAX as u64))?;
+						io::copy(&mut content, &mut zip)?;
+					}
+					ZipEntry::Data(data) => {
+						zip.start_file(name, option)?;
+						zip.write_all(&data)?;
+					}
+				}
+			}
+			zip.finish()?.sync_all()?;
+			Result::<_>::Ok(())
+		}).await?
 	}
 
 	pub async fn current_config_file(&self) -> Result<File> {
@@ -226,8 +288,12 @@ impl MinecraftConfig {
 		let mut cmd = Command::new(&self.java);
 		cmd.args(&self.jvm_args);
 		cmd.arg(format!("-Xmx{}M", self.max_ram));
-		cmd.arg("-jar");
-		cmd.arg(&self.server_file);
+		if self.launch_args.is_empty() {
+			cmd.arg("-jar");
+			cmd.arg(&self.server_file);
+		} else {
+			cmd.args(&self.launch_args);
+		}
 		cmd.args(&self.args);
 		cmd.kill_on_drop(true);
 		cmd.current_dir(self.canonicalized("")?);
@@ -325,4 +391,13 @@ impl MinecraftConfig {
 	/*pub fn build(self) -> Result<MinecraftServer> {
 		MinecraftServer::new(None)
 	}*/
+}
+
+/// Path relative to folder separated by `/`
+pub fn relative_name(parent: &Path, path: &Path) -> String {
+	path.strip_prefix(parent).unwrap_or(path)
+		.components()
+		.map(|it| it.as_os_str().to_string_lossy())
+		.collect::<Vec<_>>()
+		.join("/")
 }
\ No newline at end of file
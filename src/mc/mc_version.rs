@@ -1,34 +1,307 @@
-#[allow(clippy::needless_lifetimes)] // sometime mc_version need static lifetime
-pub fn java_for<'a>(mc_version: &'a str) -> Option<&'static JavaVersionForMc> {
-	// mc version eg. 1.19 = 19
-	let version = mc_version.split('.').nth(1)?.parse::<u8>().ok()?;
-	if version <= 12 {
-		Some(MC12)
-	} else if version <= 16 {
-		Some(MC16)
-	} else {
-		Some(MC18)
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+use crate::mc::mojang;
+
+/// Release version eg. 1.20.4 or 26.1 (year based version since 2026)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReleaseVersion {
+	pub major: u16,
+	pub minor: u16,
+	pub patch: u16,
+}
+
+impl ReleaseVersion {
+	pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+		Self { major, minor, patch }
+	}
+
+	fn parse(version: &str) -> Option<Self> {
+		let mut part = version.split('.');
+		let major = part.next()?.parse().ok()?;
+		let minor = part.next()?.parse().ok()?;
+		let patch = match part.next() {
+			Some(it) => { it.parse().ok()? }
+			None => { 0 }
+		};
+		if part.next().is_some() {
+			return None;
+		}
+		Some(Self { major, minor, patch })
+	}
+}
+
+impl Display for ReleaseVersion {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		if self.patch == 0 {
+			write!(f, "{}.{}", self.major, self.minor)
+		} else {
+			write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+		}
+	}
+}
+
+/// Kind of development version that published before release (ordered from oldest)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PreReleaseKind {
+	/// 26.1-snapshot-1
+	Snapshot,
+	/// 1.20.5-pre1 or 1.14 Pre-Release 2
+	PreRelease,
+	/// 1.20.5-rc1
+	ReleaseCandidate,
+}
+
+/// Minecraft version
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum McVersion {
+	Release(ReleaseVersion),
+	PreRelease {
+		base: ReleaseVersion,
+		kind: PreReleaseKind,
+		number: u16,
+	},
+	/// Weekly snapshot eg. 23w14a
+	Snapshot {
+		year: u8,
+		week: u8,
+		build: char,
+	},
+}
+
+impl McVersion {
+	pub fn parse(version: &str) -> Option<Self> {
+		let version = version.trim();
+		if let Some(snapshot) = Self::parse_snapshot(version) {
+			return Some(snapshot);
+		}
+		let (base, suffix) = match version.find(['-', ' ']) {
+			Some(idx) => { (&version[..idx], Some(&version[idx + 1..])) }
+			None => { (version, None) }
+		};
+		let base = ReleaseVersion::parse(base)?;
+		let suffix = match suffix {
+			Some(it) => { it.to_ascii_lowercase() }
+			None => { return Some(McVersion::Release(base)); }
+		};
+		let (kind, rest) = if let Some(rest) = suffix.strip_prefix("snapshot") {
+			(PreReleaseKind::Snapshot, rest)
+		} else if let Some(rest) = suffix.strip_prefix("pre-release") {
+			(PreReleaseKind::PreRelease, rest)
+		} else if let Some(rest) = suffix.strip_prefix("pre") {
+			(PreReleaseKind::PreRelease, rest)
+		} else if let Some(rest) = suffix.strip_prefix("rc") {
+			(PreReleaseKind::ReleaseCandidate, rest)
+		} else {
+			return None;
+		};
+		let number = rest.trim_start_matches(['-', ' ']).parse().ok()?;
+		Some(McVersion::PreRelease { base, kind, number })
+	}
+
+	fn parse_snapshot(version: &str) -> Option<Self> {
+		let (year, rest) = version.split_once('w')?;
+		if year.len() != 2 || rest.len() != 3 {
+			return None;
+		}
+		let year = year.parse().ok()?;
+		let week = rest.get(..2)?.parse().ok()?;
+		let build = rest.chars().nth(2)?;
+		if !build.is_ascii_lowercase() {
+			return None;
+		}
+		Some(McVersion::Snapshot { year, week, build })
+	}
+
+	/// Release this version belong to; weekly snapshot doesn't have one
+	pub fn release(&self) -> Option<ReleaseVersion> {
+		match self {
+			McVersion::Release(it) => { Some(*it) }
+			McVersion::PreRelease { base, .. } => { Some(*base) }
+			McVersion::Snapshot { .. } => { None }
+		}
+	}
+
+	/// Java requirement from table in this file
+	pub fn java_requirement(&self) -> JavaVersionForMc {
+		match self {
+			McVersion::Release(it) | McVersion::PreRelease { base: it, .. } => {
+				let found = RELEASE_JAVA.iter().find(|(since, _)| it >= since);
+				found.map(|(_, java)| java.clone()).unwrap_or(MC12)
+			}
+			McVersion::Snapshot { year, week, .. } => {
+				let found = SNAPSHOT_JAVA.iter().find(|(since, _)| (*year, *week) >= *since);
+				found.map(|(_, java)| java.clone()).unwrap_or(MC12)
+			}
+		}
+	}
+}
+
+impl PartialOrd for McVersion {
+	/// Weekly snapshot can only compare with other weekly snapshot
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		match (self, other) {
+			(McVersion::Snapshot { year, week, build }, McVersion::Snapshot { year: y, week: w, build: b }) => {
+				Some((year, week, build).cmp(&(y, w, b)))
+			}
+			(McVersion::Snapshot { .. }, _) | (_, McVersion::Snapshot { .. }) => { None }
+			(a, b) => {
+				let base = a.release()?.cmp(&b.release()?);
+				if base != Ordering::Equal {
+					return Some(base);
+				}
+				Some(match (a, b) {
+					(McVersion::Release(_), McVersion::Release(_)) => { Ordering::Equal }
+					(McVersion::Release(_), _) => { Ordering::Greater }
+					(_, McVersion::Release(_)) => { Ordering::Less }
+					(McVersion::PreRelease { kind, number, .. }, McVersion::PreRelease { kind: k, number: n, .. }) => {
+						(kind, number).cmp(&(k, n))
+					}
+					_ => { unreachable!() }
+				})
+			}
+		}
 	}
 }
 
-static MC12: &JavaVersionForMc = &JavaVersionForMc {
-	supported: &[8, 11, 17],
+impl Display for McVersion {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			McVersion::Release(it) => { write!(f, "{it}") }
+			McVersion::PreRelease { base, kind, number } => {
+				match kind {
+					PreReleaseKind::Snapshot => { write!(f, "{base}-snapshot-{number}") }
+					PreReleaseKind::PreRelease => { write!(f, "{base}-pre{number}") }
+					PreReleaseKind::ReleaseCandidate => { write!(f, "{base}-rc{number}") }
+				}
+			}
+			McVersion::Snapshot { year, week, build } => { write!(f, "{year:02}w{week:02}{build}") }
+		}
+	}
+}
+
+/// Java requirement from table only, see [java_requirement] to use data from mojang
+pub fn java_for(mc_version: &str) -> Option<JavaVersionForMc> {
+	McVersion::parse(mc_version).map(|it| it.java_requirement())
+}
+
+/// Java requirement for minecraft version,
+/// recommended version will come from mojang's `javaVersion` when it's available
+pub async fn java_requirement(mc_version: &str) -> Option<JavaVersionForMc> {
+	let from_table = java_for(mc_version);
+	let from_mojang = mojang::java_major(mc_version).await;
+	match (from_table, from_mojang) {
+		(Some(mut java), Some(major)) => {
+			if !java.supported.contains(&major) {
+				java.supported.to_mut().push(major);
+			}
+			java.recommended = major;
+			Some(java)
+		}
+		(None, Some(major)) => {
+			Some(JavaVersionForMc {
+				supported: Cow::Owned(vec![major]),
+				recommended: major,
+			})
+		}
+		(table, None) => { table }
+	}
+}
+
+/// Newest first; each entry apply to release since its version
+static RELEASE_JAVA: &[(ReleaseVersion, JavaVersionForMc)] = &[
+	(ReleaseVersion::new(26, 1, 0), MC26),
+	(ReleaseVersion::new(1, 20, 5), MC20_5),
+	(ReleaseVersion::new(1, 18, 0), MC18),
+	(ReleaseVersion::new(1, 17, 0), MC17),
+	(ReleaseVersion::new(1, 13, 0), MC16),
+];
+
+/// Same as [RELEASE_JAVA] but for weekly snapshot (year, week)
+static SNAPSHOT_JAVA: &[((u8, u8), JavaVersionForMc)] = &[
+	((24, 14), MC20_5),
+	((21, 44), MC18),
+	((21, 19), MC17),
+	((17, 43), MC16),
+];
+
+const MC12: JavaVersionForMc = JavaVersionForMc {
+	supported: Cow::Borrowed(&[8, 11, 17]),
 	recommended: 8,
 };
 
-static MC16: &JavaVersionForMc = &JavaVersionForMc {
-	supported: &[8, 11, 17],
+const MC16: JavaVersionForMc = JavaVersionForMc {
+	supported: Cow::Borrowed(&[8, 11, 17]),
 	recommended: 11,
 };
 
-static MC18: &JavaVersionForMc = &JavaVersionForMc {
-	supported: &[17],
+const MC17: JavaVersionForMc = JavaVersionForMc {
+	supported: Cow::Borrowed(&[16, 17]),
+	recommended: 17,
+};
+
+const MC18: JavaVersionForMc = JavaVersionForMc {
+	supported: Cow::Borrowed(&[17, 21]),
 	recommended: 17,
 };
 
-/// Supported java version for minecraft  
+const MC20_5: JavaVersionForMc = JavaVersionForMc {
+	supported: Cow::Borrowed(&[21, 25]),
+	recommended: 21,
+};
+
+const MC26: JavaVersionForMc = JavaVersionForMc {
+	supported: Cow::Borrowed(&[25]),
+	recommended: 25,
+};
+
+/// Velocity 3.4+ require java 21; waterfall run fine on both
+pub static PROXY: JavaVersionForMc = JavaVersionForMc {
+	supported: Cow::Borrowed(&[17, 21]),
+	recommended: 21,
+};
+
+/// Supported java version for minecraft
 /// eg. 8 = java8; 17 for java17
+#[derive(Debug, Clone)]
 pub struct JavaVersionForMc {
-	pub supported: &'static [u8],
+	pub supported: Cow<'static, [u8]>,
 	pub recommended: u8,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+	use crate::mc::mc_version::{java_for, McVersion, PreReleaseKind, ReleaseVersion};
+
+	#[test]
+	fn test_parse_version() {
+		assert_eq!(McVersion::parse("1.19"), Some(McVersion::Release(ReleaseVersion::new(1, 19, 0))));
+		assert_eq!(McVersion::parse("26.1.1"), Some(McVersion::Release(ReleaseVersion::new(26, 1, 1))));
+		assert_eq!(McVersion::parse("23w14a"), Some(McVersion::Snapshot { year: 23, week: 14, build: 'a' }));
+		assert_eq!(McVersion::parse("1.20.5-pre1"), Some(McVersion::PreRelease {
+			base: ReleaseVersion::new(1, 20, 5),
+			kind: PreReleaseKind::PreRelease,
+			number: 1,
+		}));
+		assert_eq!(McVersion::parse("1.14 Pre-Release 2").map(|it| it.to_string()), Some("1.14-pre2".to_string()));
+		assert_eq!(McVersion::parse("26.1-snapshot-3").map(|it| it.to_string()), Some("26.1-snapshot-3".to_string()));
+		assert_eq!(McVersion::parse("3D Shareware v1.34"), None);
+		assert!(McVersion::parse("1.20.5-rc1") > McVersion::parse("1.20.5-pre4"));
+		assert!(McVersion::parse("1.20.5") > McVersion::parse("1.20.5-rc1"));
+		assert!(McVersion::parse("26.1") > McVersion::parse("1.21.10"));
+	}
+
+	#[test]
+	fn test_java_for() {
+		assert_eq!(java_for("1.12.2").unwrap().recommended, 8);
+		assert_eq!(java_for("1.16.5").unwrap().recommended, 11);
+		assert_eq!(java_for("1.20.4").unwrap().recommended, 17);
+		assert_eq!(java_for("1.20.5").unwrap().recommended, 21);
+		assert_eq!(java_for("1.20.5-pre1").unwrap().recommended, 21);
+		assert_eq!(java_for("23w14a").unwrap().recommended, 17);
+		assert_eq!(java_for("24w14a").unwrap().recommended, 21);
+		assert_eq!(java_for("26.1").unwrap().recommended, 25);
+		assert!(java_for("latest").is_none());
+	}
+}
@@ -1,2 +1,11 @@
+pub mod curseforge;
+pub mod forge;
 pub mod mc_config;
 pub mod mc_version;
+pub mod mod_metadata;
+pub mod modpack;
+pub mod modrinth;
+pub mod mojang;
+pub mod multimc;
+pub mod papermc;
+pub mod version_cache;
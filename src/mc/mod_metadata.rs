@@ -0,0 +1,547 @@
+use std::io::{Read, Seek};
+
+use hashbrown::HashMap;
+use serde::Deserialize;
+use serde_json::Value;
+use zip::ZipArchive;
+
+use crate::instance::mc_mod::{DependencyKind, ModDependency, ModLoader, ModSide};
+
+/// Metadata declared by mod or plugin inside its jar
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModMetadata {
+	pub id: String,
+	pub name: String,
+	pub version: String,
+	pub authors: Vec<String>,
+	pub dependencies: Vec<ModDependency>,
+	pub side: ModSide,
+	pub loader: ModLoader,
+}
+
+/// Try every known metadata file; empty if jar doesn't have any.
+/// Jar may declare several mods (forge `[[mods]]`, `mcmod.info` list), the first one is the main mod
+pub fn read_metadata<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Vec<ModMetadata> {
+	if let Some(it) = read_entry(zip, "META-INF/neoforge.mods.toml") {
+		return parse_mods_toml(&it, true);
+	}
+	if let Some(it) = read_entry(zip, "META-INF/mods.toml") {
+		return parse_mods_toml(&it, false);
+	}
+	if let Some(it) = read_entry(zip, "quilt.mod.json") {
+		return parse_quilt(&it).into_iter().collect();
+	}
+	if let Some(it) = read_entry(zip, "fabric.mod.json") {
+		return parse_fabric(&it).into_iter().collect();
+	}
+	if let Some(it) = read_entry(zip, "mcmod.info") {
+		return parse_mcmod_info(&it);
+	}
+	if let Some(it) = read_entry(zip, "plugin.yml") {
+		return parse_plugin_yml(&it).into_iter().collect();
+	}
+	vec![]
+}
+
+fn read_entry<R: Read + Seek>(zip: &mut ZipArchive<R>, name: &str) -> Option<String> {
+	let mut entry = zip.by_name(name).ok()?;
+	let mut content = String::new();
+	entry.read_to_string(&mut content).ok()?;
+	Some(content)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModsToml {
+	#[serde(default)]
+	client_side_only: bool,
+	#[serde(default)]
+	mods: Vec<ModsTomlMod>,
+	/// mod id -> dependencies
+	#[serde(default)]
+	dependencies: HashMap<String, Vec<ModsTomlDependency>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModsTomlMod {
+	mod_id: String,
+	#[serde(default)]
+	version: String,
+	#[serde(default)]
+	display_name: String,
+	#[serde(default)]
+	authors: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModsTomlDependency {
+	mod_id: String,
+	/// forge
+	#[serde(default)]
+	mandatory: Option<bool>,
+	/// neoforge: `required`, `optional`, `incompatible` or `discouraged`
+	#[serde(default, rename = "type")]
+	typ: Option<String>,
+	#[serde(default)]
+	version_range: String,
+	/// `BOTH`, `CLIENT` or `SERVER`
+	#[serde(default)]
+	side: String,
+}
+
+/// `META-INF/mods.toml` (forge and neoforge before 20.5) or `META-INF/neoforge.mods.toml`, every `[[mods]]` entry
+pub fn parse_mods_toml(content: &str, neoforge: bool) -> Vec<ModMetadata> {
+	let Ok(mut toml) = toml::from_str::<ModsToml>(content) else {
+		return vec![];
+	};
+	// early neoforge still use mods.toml
+	let neoforge = neoforge || toml.dependencies.values().flatten().any(|it| it.mod_id == "neoforge");
+	let side = if toml.client_side_only { ModSide::Client } else { ModSide::Both };
+	toml.mods.into_iter()
+		.map(|it| {
+			let dependencies = toml.dependencies
+				.remove(&it.mod_id)
+				.unwrap_or_default()
+				.into_iter()
+				.map(|it| {
+					let kind = match (it.mandatory, it.typ.as_deref()) {
+						(_, Some("optional" | "discouraged")) | (Some(false), None) => { DependencyKind::Optional }
+						(_, Some("incompatible")) => { DependencyKind::Incompatible }
+						_ => { DependencyKind::Required }
+					};
+					ModDependency {
+						id: it.mod_id,
+						version_range: it.version_range,
+						kind,
+						side: parse_side(&it.side),
+					}
+				})
+				.collect();
+			ModMetadata {
+				id: it.mod_id,
+				name: it.display_name,
+				version: it.version,
+				authors: it.authors.split(',').map(|it| it.trim().to_string()).filter(|it| !it.is_empty()).collect(),
+				dependencies,
+				side,
+				loader: if neoforge { ModLoader::NeoForge } else { ModLoader::Forge },
+			}
+		})
+		.collect()
+}
+
+fn parse_side(side: &str) -> ModSide {
+	match side.to_ascii_lowercase().as_str() {
+		"client" => { ModSide::Client }
+		"server" | "dedicated_server" => { ModSide::Server }
+		_ => { ModSide::Both }
+	}
+}
+
+#[derive(Deserialize)]
+struct FabricMod {
+	id: String,
+	#[serde(default)]
+	version: String,
+	#[serde(default)]
+	name: String,
+	/// string or `{ "name": "" }`
+	#[serde(default)]
+	authors: Vec<Value>,
+	#[serde(default)]
+	environment: String,
+	#[serde(default)]
+	depends: HashMap<String, Value>,
+	#[serde(default)]
+	recommends: HashMap<String, Value>,
+	#[serde(default)]
+	breaks: HashMap<String, Value>,
+}
+
+/// Fabric version predicate can be list of alternatives
+fn fabric_range(value: Value) -> String {
+	match value {
+		Value::String(it) if it == "*" => { String::new() }
+		Value::String(it) => { it }
+		Value::Array(it) => {
+			it.into_iter().filter_map(|it| it.as_str().map(|it| it.to_string())).collect::<Vec<_>>().join(" || ")
+		}
+		_ => { String::new() }
+	}
+}
+
+fn person(value: &Value) -> Option<String> {
+	match value {
+		Value::String(it) => { Some(it.clone()) }
+		Value::Object(it) => { it.get("name")?.as_str().map(|it| it.to_string()) }
+		_ => { None }
+	}
+}
+
+/// `fabric.mod.json`
+pub fn parse_fabric(content: &str) -> Option<ModMetadata> {
+	let fabric: FabricMod = serde_json::from_str(content).ok()?;
+	let mut dependencies = vec![];
+	for (deps, kind) in [
+		(fabric.depends, DependencyKind::Required),
+		(fabric.recommends, DependencyKind::Optional),
+		(fabric.breaks, DependencyKind::Incompatible),
+	] {
+		for (id, range) in deps {
+			dependencies.push(ModDependency { id, version_range: fabric_range(range), kind, side: ModSide::Both });
+		}
+	}
+	dependencies.sort_by(|a, b| a.id.cmp(&b.id));
+	Some(ModMetadata {
+		id: fabric.id,
+		name: fabric.name,
+		version: fabric.version,
+		authors: fabric.authors.iter().filter_map(person).collect(),
+		dependencies,
+		side: parse_side(&fabric.environment),
+		loader: ModLoader::Fabric,
+	})
+}
+
+#[derive(Deserialize)]
+struct QuiltMod {
+	quilt_loader: QuiltLoader,
+	#[serde(default)]
+	minecraft: Option<QuiltMinecraft>,
+}
+
+#[derive(Deserialize)]
+struct QuiltLoader {
+	id: String,
+	#[serde(default)]
+	version: String,
+	#[serde(default)]
+	metadata: Option<QuiltMetadata>,
+	#[serde(default)]
+	depends: Vec<Value>,
+	#[serde(default)]
+	breaks: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct QuiltMetadata {
+	#[serde(default)]
+	name: String,
+	/// name -> role
+	#[serde(default)]
+	contributors: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct QuiltMinecraft {
+	#[serde(default)]
+	environment: String,
+}
+
+/// Dependency is either mod id or `{ "id": "", "versions": "", "optional": false }`
+fn quilt_dependency(value: Value, kind: DependencyKind) -> Option<ModDependency> {
+	match value {
+		Value::String(id) => {
+			Some(ModDependency { id, version_range: String::new(), kind, side: ModSide::Both })
+		}
+		Value::Object(mut it) => {
+			let id = it.get("id")?.as_str()?.to_string();
+			let optional = it.get("optional").and_then(|it| it.as_bool()).unwrap_or(false);
+			Some(ModDependency {
+				id,
+				version_range: it.remove("versions").map(fabric_range).unwrap_or_default(),
+				kind: if optional && kind == DependencyKind::Required { DependencyKind::Optional } else { kind },
+				side: ModSide::Both,
+			})
+		}
+		_ => { None }
+	}
+}
+
+/// `quilt.mod.json`
+pub fn parse_quilt(content: &str) -> Option<ModMetadata> {
+	let quilt: QuiltMod = serde_json::from_str(content).ok()?;
+	let loader = quilt.quilt_loader;
+	let dependencies = loader.depends.into_iter().filter_map(|it| quilt_dependency(it, DependencyKind::Required))
+		.chain(loader.breaks.into_iter().filter_map(|it| quilt_dependency(it, DependencyKind::Incompatible)))
+		.collect();
+	let (name, authors) = match loader.metadata {
+		Some(it) => {
+			let mut authors: Vec<String> = it.contributors.into_keys().collect();
+			authors.sort();
+			(it.name, authors)
+		}
+		None => { (String::new(), vec![]) }
+	};
+	Some(ModMetadata {
+		id: loader.id,
+		name,
+		version: loader.version,
+		authors,
+		dependencies,
+		side: quilt.minecraft.map(|it| parse_side(&it.environment)).unwrap_or_default(),
+		loader: ModLoader::Quilt,
+	})
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum McModInfo {
+	List(Vec<McModInfoMod>),
+	/// modListVersion 2
+	Wrapped {
+		#[serde(rename = "modList")]
+		mod_list: Vec<McModInfoMod>,
+	},
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct McModInfoMod {
+	#[serde(rename = "modid")]
+	mod_id: String,
+	#[serde(default)]
+	name: String,
+	#[serde(default)]
+	version: String,
+	#[serde(default)]
+	author_list: Vec<String>,
+	/// `modid` or `modid@[1.0,)`
+	#[serde(default)]
+	required_mods: Vec<String>,
+}
+
+/// `mcmod.info` from legacy forge (1.12 and older), every mod in list
+pub fn parse_mcmod_info(content: &str) -> Vec<ModMetadata> {
+	let Ok(info) = serde_json::from_str::<McModInfo>(content) else {
+		return vec![];
+	};
+	let mods = match info {
+		McModInfo::List(it) => { it }
+		McModInfo::Wrapped { mod_list } => { mod_list }
+	};
+	mods.into_iter()
+		.map(|it| {
+			let dependencies = it.required_mods
+				.into_iter()
+				.map(|it| {
+					let (id, range) = it.split_once('@').unwrap_or((&it, ""));
+					ModDependency { id: id.to_string(), version_range: range.to_string(), kind: DependencyKind::Required, side: ModSide::Both }
+				})
+				.collect();
+			ModMetadata {
+				id: it.mod_id,
+				name: it.name,
+				version: it.version,
+				authors: it.author_list,
+				dependencies,
+				side: ModSide::Both,
+				loader: ModLoader::Forge,
+			}
+		})
+		.collect()
+}
+
+#[derive(Deserialize)]
+struct PluginYml {
+	name: String,
+	#[serde(default)]
+	version: Option<serde_yaml::Value>,
+	#[serde(default)]
+	author: Option<String>,
+	#[serde(default)]
+	authors: Vec<String>,
+	#[serde(default)]
+	depend: Vec<String>,
+	#[serde(default)]
+	softdepend: Vec<String>,
+}
+
+/// Bukkit `plugin.yml`
+pub fn parse_plugin_yml(content: &str) -> Option<ModMetadata> {
+	let plugin: PluginYml = serde_yaml::from_str(content).ok()?;
+	let version = match plugin.version {
+		Some(serde_yaml::Value::String(it)) => { it }
+		// `version: 1.0` is parsed as number
+		Some(serde_yaml::Value::Number(it)) => { it.to_string() }
+		_ => { String::new() }
+	};
+	let dependencies = plugin.depend.into_iter().map(|it| (it, DependencyKind::Required))
+		.chain(plugin.softdepend.into_iter().map(|it| (it, DependencyKind::Optional)))
+		.map(|(id, kind)| ModDependency { id, version_range: String::new(), kind, side: ModSide::Server })
+		.collect();
+	Some(ModMetadata {
+		id: plugin.name.clone(),
+		name: plugin.name,
+		version,
+		authors: plugin.author.into_iter().chain(plugin.authors).collect(),
+		dependencies,
+		side: ModSide::Server,
+		loader: ModLoader::Bukkit,
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use std::io::{Cursor, Write};
+
+	use zip::write::FileOptions;
+	use zip::{ZipArchive, ZipWriter};
+
+	use crate::instance::mc_mod::{DependencyKind, MinecraftMod, ModLoader, ModSide};
+	use crate::mc::mod_metadata::{parse_fabric, parse_mcmod_info, parse_mods_toml, parse_plugin_yml, parse_quilt};
+
+	static FORGE_TOML: &str = r#"
+modLoader = "javafml"
+loaderVersion = "[47,)"
+
+[[mods]]
+modId = "create"
+version = "${file.jarVersion}"
+displayName = "Create"
+authors = "simibubi, Kryppers"
+
+[[mods]]
+modId = "ponder"
+version = "0.6"
+
+[[dependencies.create]]
+modId = "forge"
+mandatory = true
+versionRange = "[47,)"
+ordering = "NONE"
+side = "BOTH"
+
+[[dependencies.create]]
+modId = "jei"
+mandatory = false
+versionRange = "[15,)"
+side = "CLIENT"
+
+[[dependencies.ponder]]
+modId = "minecraft"
+mandatory = true
+versionRange = "[1.20.1,1.20.2)"
+side = "SERVER"
+"#;
+
+	#[test]
+	fn test_mods_toml() {
+		let mods = parse_mods_toml(FORGE_TOML, false);
+		assert_eq!(mods.len(), 2);
+		let create = &mods[0];
+		assert_eq!(create.id, "create");
+		assert_eq!(create.authors, vec!["simibubi", "Kryppers"]);
+		assert_eq!(create.loader, ModLoader::Forge);
+		assert_eq!(create.dependencies.len(), 2);
+		assert_eq!(create.dependencies[0].kind, DependencyKind::Required);
+		assert_eq!(create.dependencies[0].side, ModSide::Both);
+		assert_eq!(create.dependencies[1].kind, DependencyKind::Optional);
+		assert_eq!(create.dependencies[1].side, ModSide::Client);
+		assert_eq!(mods[1].id, "ponder");
+		assert_eq!(mods[1].dependencies[0].side, ModSide::Server);
+
+		let mods = parse_mods_toml(r#"
+clientSideOnly = true
+[[mods]]
+modId = "example"
+version = "1.0"
+[[dependencies.example]]
+modId = "neoforge"
+type = "required"
+versionRange = "[20.4,)"
+[[dependencies.example]]
+modId = "sodium"
+type = "incompatible"
+[[dependencies.example]]
+modId = "jei"
+type = "discouraged"
+"#, true);
+		let meta = &mods[0];
+		assert_eq!(meta.loader, ModLoader::NeoForge);
+		assert_eq!(meta.side, ModSide::Client);
+		let kinds: Vec<DependencyKind> = meta.dependencies.iter().map(|it| it.kind).collect();
+		assert_eq!(kinds, vec![DependencyKind::Required, DependencyKind::Incompatible, DependencyKind::Optional]);
+
+		// neoforge dependency in legacy mods.toml
+		let mods = parse_mods_toml("[[mods]]\nmodId = \"old\"\n[[dependencies.old]]\nmodId = \"neoforge\"\n", false);
+		assert_eq!(mods[0].loader, ModLoader::NeoForge);
+		assert!(parse_mods_toml("not toml", false).is_empty());
+	}
+
+	#[test]
+	fn test_jar_version_fallback() {
+		let mut zip = ZipWriter::new(Cursor::new(vec![]));
+		zip.start_file("META-INF/MANIFEST.MF", FileOptions::default()).unwrap();
+		zip.write_all(b"Manifest-Version: 1.0\nImplementation-Version: 0.5.1.f\n").unwrap();
+		zip.start_file("META-INF/mods.toml", FileOptions::default()).unwrap();
+		zip.write_all(FORGE_TOML.as_bytes()).unwrap();
+		let zip = ZipArchive::new(zip.finish().unwrap()).unwrap();
+
+		let parsed = MinecraftMod::parse_zip(zip, "create-1.20.1-0.5.1.f.jar");
+		assert_eq!(parsed.id, "create");
+		assert_eq!(parsed.name, "Create");
+		assert_eq!(parsed.version, "0.5.1.f");
+		assert_eq!(parsed.provides.len(), 1);
+		assert_eq!(parsed.provides[0].id, "ponder");
+		assert_eq!(parsed.provides[0].version, "0.6");
+		// dependencies of every mod in jar
+		let ids: Vec<&str> = parsed.dependencies.iter().map(|it| it.id.as_str()).collect();
+		assert_eq!(ids, vec!["forge", "jei", "minecraft"]);
+	}
+
+	#[test]
+	fn test_fabric() {
+		let meta = parse_fabric(r#"{
+			"schemaVersion": 1, "id": "sodium", "version": "0.5.8", "name": "Sodium",
+			"authors": ["JellySquid", {"name": "IMS"}], "environment": "client",
+			"depends": {"fabricloader": ">=0.12.0", "minecraft": ["1.20.3", "1.20.4"]},
+			"breaks": {"optifabric": "*"}
+		}"#).unwrap();
+		assert_eq!(meta.id, "sodium");
+		assert_eq!(meta.authors, vec!["JellySquid", "IMS"]);
+		assert_eq!(meta.side, ModSide::Client);
+		assert_eq!(meta.loader, ModLoader::Fabric);
+		assert_eq!(meta.dependencies[1].id, "minecraft");
+		assert_eq!(meta.dependencies[1].version_range, "1.20.3 || 1.20.4");
+		assert_eq!(meta.dependencies[2].kind, DependencyKind::Incompatible);
+		assert!(meta.dependencies[2].version_range.is_empty());
+	}
+
+	#[test]
+	fn test_quilt() {
+		let meta = parse_quilt(r#"{
+			"schema_version": 1,
+			"quilt_loader": {
+				"group": "org.example", "id": "example", "version": "1.0.0",
+				"metadata": {"name": "Example", "contributors": {"Someone": "Owner"}},
+				"depends": ["quilt_loader", {"id": "minecraft", "versions": ">=1.20"}, {"id": "modmenu", "optional": true}]
+			},
+			"minecraft": {"environment": "dedicated_server"}
+		}"#).unwrap();
+		assert_eq!(meta.name, "Example");
+		assert_eq!(meta.side, ModSide::Server);
+		assert_eq!(meta.dependencies.len(), 3);
+		assert_eq!(meta.dependencies[1].version_range, ">=1.20");
+		assert_eq!(meta.dependencies[2].kind, DependencyKind::Optional);
+	}
+
+	#[test]
+	fn test_legacy_and_plugin() {
+		let meta = parse_mcmod_info(r#"[{"modid": "jei", "name": "Just Enough Items", "version": "4.16.1",
+			"authorList": ["mezz"], "requiredMods": ["Forge@[14.23.5,)"]}]"#);
+		assert_eq!(meta[0].dependencies[0].id, "Forge");
+		assert_eq!(meta[0].dependencies[0].version_range, "[14.23.5,)");
+		let meta = parse_mcmod_info(r#"{"modListVersion": 2, "modList": [{"modid": "ic2"}, {"modid": "ic2-classic"}]}"#);
+		assert_eq!(meta.len(), 2);
+		assert_eq!(meta[0].id, "ic2");
+
+		let meta = parse_plugin_yml("name: LuckPerms\nversion: 5.4\nauthor: Luck\nsoftdepend: [Vault]\n").unwrap();
+		assert_eq!(meta.version, "5.4");
+		assert_eq!(meta.authors, vec!["Luck"]);
+		assert_eq!(meta.loader, ModLoader::Bukkit);
+		assert_eq!(meta.dependencies[0].kind, DependencyKind::Optional);
+	}
+}
@@ -0,0 +1,316 @@
+use std::io;
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use hashbrown::HashMap;
+use reqwest::Client;
+use serde::Deserialize;
+use tokio::fs::{create_dir_all, remove_file};
+use tokio::task::spawn_blocking;
+use tracing::{debug, warn};
+use zip::ZipArchive;
+
+use crate::instance::mc_instance::{McInstance, ModType};
+use crate::instance::mc_mod::{MinecraftMod, ModSide, ModSource};
+use crate::mc::curseforge;
+use crate::mc::curseforge::{CurseForgeFile, ManualDownload};
+use crate::util::artifact_cache;
+use crate::util::http::{DownloadProgress, FileHash, new_client};
+
+static MRPACK_INDEX: &str = "modrinth.index.json";
+static CURSEFORGE_MANIFEST: &str = "manifest.json";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MrpackIndex {
+	name: String,
+	files: Vec<MrpackFile>,
+	/// `minecraft`, `forge`, `neoforge`, `fabric-loader` or `quilt-loader` -> version
+	dependencies: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct MrpackFile {
+	/// Relative to instance
+	path: String,
+	/// `sha1` and `sha512`
+	hashes: HashMap<String, String>,
+	#[serde(default)]
+	env: Option<MrpackEnv>,
+	downloads: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct MrpackEnv {
+	/// `required`, `optional` or `unsupported`
+	server: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseForgeManifest {
+	#[serde(default)]
+	manifest_type: String,
+	name: String,
+	minecraft: CurseForgeMinecraft,
+	files: Vec<CurseForgePackFile>,
+	#[serde(default)]
+	overrides: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseForgeMinecraft {
+	version: String,
+	#[serde(default)]
+	mod_loaders: Vec<CurseForgeLoader>,
+}
+
+#[derive(Deserialize)]
+struct CurseForgeLoader {
+	/// eg. `forge-47.2.0`
+	id: String,
+	#[serde(default)]
+	primary: bool,
+}
+
+#[derive(Deserialize)]
+struct CurseForgePackFile {
+	#[serde(rename = "projectID")]
+	project_id: u64,
+	#[serde(rename = "fileID")]
+	file_id: u64,
+	#[serde(default = "default_required")]
+	required: bool,
+}
+
+fn default_required() -> bool { true }
+
+/// Modrinth `.mrpack` or CurseForge modpack zip
+pub struct Modpack {
+	pub name: String,
+	pub mc_version: String,
+	pub mod_type: ModType,
+	/// Files from modrinth index which server needs
+	downloads: Vec<MrpackFile>,
+	/// (project, file) from curseforge manifest, see [Self::resolve]
+	curseforge: Vec<(u64, u64)>,
+	curseforge_files: Vec<CurseForgeFile>,
+	/// Folders in archive copied into instance, later one replaces files from earlier one
+	overrides: Vec<String>,
+}
+
+impl Modpack {
+	pub async fn read(archive: impl Into<PathBuf>) -> Result<Self> {
+		let archive = archive.into();
+		spawn_blocking(move || {
+			let mut zip = ZipArchive::new(std::fs::File::open(&archive)?)?;
+			if let Some(index) = read_entry(&mut zip, MRPACK_INDEX)? {
+				let index: MrpackIndex = serde_json::from_slice(&index)?;
+				let Some(mc_version) = index.dependencies.get("minecraft").cloned() else {
+					bail!("Modpack doesn't specify minecraft version");
+				};
+				return Ok(Self {
+					name: index.name,
+					mc_version,
+					mod_type: mrpack_loader(&index.dependencies)?,
+					downloads: index.files.into_iter()
+						.filter(|it| !it.env.as_ref().is_some_and(|env| env.server == "unsupported"))
+						.collect(),
+					curseforge: vec![],
+					curseforge_files: vec![],
+					overrides: vec!["overrides".to_string(), "server-overrides".to_string()],
+				});
+			}
+			if let Some(manifest) = read_entry(&mut zip, CURSEFORGE_MANIFEST)? {
+				let manifest: CurseForgeManifest = serde_json::from_slice(&manifest)?;
+				if manifest.manifest_type != "minecraftModpack" {
+					bail!("manifest.json is not a curseforge modpack manifest");
+				}
+				let loader = manifest.minecraft.mod_loaders.iter()
+					.find(|it| it.primary)
+					.or_else(|| manifest.minecraft.mod_loaders.first());
+				return Ok(Self {
+					name: manifest.name,
+					mod_type: match loader {
+						Some(loader) => { curseforge_loader(&loader.id)? }
+						None => { ModType::Vanilla }
+					},
+					mc_version: manifest.minecraft.version,
+					downloads: vec![],
+					curseforge: manifest.files.iter()
+						.filter(|it| it.required)
+						.map(|it| (it.project_id, it.file_id))
+						.collect(),
+					curseforge_files: vec![],
+					overrides: vec![manifest.overrides.unwrap_or_else(|| "overrides".to_string())],
+				});
+			}
+			bail!("Neither {MRPACK_INDEX} nor {CURSEFORGE_MANIFEST} is found in modpack")
+		}).await?
+	}
+
+	/// True if curseforge api is needed to install it
+	pub fn needs_curseforge(&self) -> bool {
+		!self.curseforge.is_empty()
+	}
+
+	/// Look up curseforge files, return files that have to be downloaded manually.
+	/// Client-only files and files that aren't mod (resource packs, shaders) are skipped
+	pub async fn resolve(&mut self, client: &Client, progress: &dyn DownloadProgress) -> io::Result<Vec<ManualDownload>> {
+		let mut manual = vec![];
+		for (project_id, file_id) in std::mem::take(&mut self.curseforge) {
+			progress.set_phase(&format!("resolving curseforge file {file_id}"));
+			let file = curseforge::file(client, project_id, file_id).await?;
+			let client_only = file.game_versions.iter().any(|it| it == "Client") && !file.game_versions.iter().any(|it| it == "Server");
+			if client_only || !file.file_name.ends_with(".jar") {
+				debug!("skipping {} from modpack", file.file_name);
+				continue;
+			}
+			if file.download_url.is_some() {
+				self.curseforge_files.push(file);
+			} else {
+				manual.push(curseforge::manual_download(client, &file).await?);
+			}
+		}
+		Ok(manual)
+	}
+
+	/// Download files (hash is verified) and apply overrides from archive into instance folder,
+	/// return mods linked to where they were downloaded from and mods put into mod folder by overrides
+	pub async fn install(&self, archive: &Path, instance: &McInstance, progress: &dyn DownloadProgress) -> Result<Vec<MinecraftMod>> {
+		let client = new_client()?;
+		let mod_dir = instance.mod_dir()?;
+		create_dir_all(&mod_dir).await?;
+		let mut mods = vec![];
+		for file in &self.downloads {
+			let Some(url) = file.downloads.first() else {
+				warn!("{} doesn't have download url", file.path);
+				continue;
+			};
+			// path is from archive, keep it inside instance
+			let target = instance.dir(&file.path)?;
+			let hash = file.hashes.get("sha512").map(|it| FileHash::Sha512(it.clone()))
+				.or_else(|| file.hashes.get("sha1").map(|it| FileHash::Sha1(it.clone())));
+			progress.set_phase(&format!("downloading {}", file.path));
+			let path = artifact_cache::fetch(&client, url, &target, hash.as_ref(), progress).await?;
+			if path.parent() != Some(mod_dir.as_path()) {
+				continue;
+			}
+			match MinecraftMod::try_parse(&path).await {
+				Ok(mut mc_mod) => {
+					mc_mod.source = modrinth_source(url);
+					mods.push(mc_mod);
+				}
+				Err(err) => { warn!("{} is not a valid mod: {err}", file.path) }
+			}
+		}
+		for mc_mod in curseforge::install(&client, &self.curseforge_files, &mod_dir, progress).await? {
+			// curseforge doesn't always tag client-only file
+			if mc_mod.side == ModSide::Client {
+				debug!("removing client-only {}", mc_mod.file_name);
+				remove_file(mod_dir.join(&mc_mod.file_name)).await.ok();
+				continue;
+			}
+			mods.push(mc_mod);
+		}
+
+		progress.set_phase("applying overrides");
+		let archive = archive.to_path_buf();
+		let folder = instance.dir("")?;
+		let overrides = self.overrides.clone();
+		spawn_blocking(move || apply_overrides(&archive, &overrides, &folder)).await??;
+		for installed in instance.scan_mods(false).await? {
+			if installed.error.is_none() && !mods.iter().any(|it| it.file_name == installed.info.file_name) {
+				debug!("{} is added by overrides", installed.info.file_name);
+				mods.push(installed.info);
+			}
+		}
+		Ok(mods)
+	}
+}
+
+fn read_entry<R: Read + Seek>(zip: &mut ZipArchive<R>, name: &str) -> Result<Option<Vec<u8>>> {
+	let mut entry = match zip.by_name(name) {
+		Ok(it) => { it }
+		Err(zip::result::ZipError::FileNotFound) => { return Ok(None); }
+		Err(err) => { return Err(err.into()); }
+	};
+	let mut data = Vec::with_capacity(entry.size() as usize);
+	entry.read_to_end(&mut data)?;
+	Ok(Some(data))
+}
+
+fn apply_overrides(archive: &Path, overrides: &[String], folder: &Path) -> Result<()> {
+	let mut zip = ZipArchive::new(std::fs::File::open(archive)?)?;
+	for prefix in overrides {
+		for i in 0..zip.len() {
+			let mut entry = zip.by_index(i)?;
+			// None if path escapes archive
+			let Some(relative) = entry.enclosed_name().and_then(|it| it.strip_prefix(prefix).ok()).map(|it| it.to_path_buf()) else {
+				continue;
+			};
+			if entry.is_dir() || relative.as_os_str().is_empty() {
+				continue;
+			}
+			let target = folder.join(relative);
+			if let Some(parent) = target.parent() {
+				std::fs::create_dir_all(parent)?;
+			}
+			io::copy(&mut entry, &mut std::fs::File::create(&target)?)?;
+		}
+	}
+	Ok(())
+}
+
+fn mrpack_loader(dependencies: &HashMap<String, String>) -> Result<ModType> {
+	if let Some(ver) = dependencies.get("neoforge") {
+		return Ok(ModType::NeoForge(ver.clone()));
+	}
+	if let Some(ver) = dependencies.get("forge") {
+		return Ok(ModType::Forge(ver.clone()));
+	}
+	if dependencies.contains_key("fabric-loader") || dependencies.contains_key("quilt-loader") {
+		bail!("Fabric and Quilt modpack aren't supported");
+	}
+	Ok(ModType::Vanilla)
+}
+
+/// Loader id from curseforge manifest eg. `forge-47.2.0`
+fn curseforge_loader(id: &str) -> Result<ModType> {
+	match id.split_once('-') {
+		Some(("forge", ver)) => { Ok(ModType::Forge(ver.to_string())) }
+		Some(("neoforge", ver)) => { Ok(ModType::NeoForge(ver.to_string())) }
+		_ => { bail!("Loader {id} isn't supported") }
+	}
+}
+
+/// Project and version from modrinth cdn url `https://cdn.modrinth.com/data/<project>/versions/<version>/<file>`
+fn modrinth_source(url: &str) -> Option<ModSource> {
+	let path = url.split_once("cdn.modrinth.com/data/")?.1;
+	let mut parts = path.split('/');
+	let project_id = parts.next()?.to_string();
+	if parts.next()? != "versions" {
+		return None;
+	}
+	let version_id = parts.next()?.to_string();
+	Some(ModSource::Modrinth { project_id, version_id })
+}
+
+#[cfg(test)]
+mod test {
+	use crate::instance::mc_instance::ModType;
+	use crate::instance::mc_mod::ModSource;
+	use crate::mc::modpack::{curseforge_loader, modrinth_source};
+
+	#[test]
+	fn test_sources() {
+		assert_eq!(modrinth_source("https://cdn.modrinth.com/data/AANobbMI/versions/4I5dKVMp/sodium.jar"),
+		           Some(ModSource::Modrinth { project_id: "AANobbMI".to_string(), version_id: "4I5dKVMp".to_string() }));
+		assert_eq!(modrinth_source("https://example.com/sodium.jar"), None);
+		assert!(matches!(curseforge_loader("forge-47.2.0"), Ok(ModType::Forge(ver)) if ver == "47.2.0"));
+		assert!(matches!(curseforge_loader("neoforge-20.4.80-beta"), Ok(ModType::NeoForge(ver)) if ver == "20.4.80-beta"));
+		assert!(curseforge_loader("fabric-0.15.0").is_err());
+	}
+}
@@ -0,0 +1,268 @@
+use std::io;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use anyhow::Result;
+use hashbrown::{HashMap, HashSet};
+use reqwest::header::USER_AGENT;
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::instance::mc_instance::ModType;
+use crate::instance::mc_mod::{MinecraftMod, ModSource};
+use crate::util::artifact_cache;
+use crate::util::config::get_config;
+use crate::util::errors::reqwest_to_io;
+use crate::util::http::{DownloadProgress, FileHash};
+
+/// Modrinth ask api user to identify themselves
+static MODRINTH_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// modrinth api root from config (`modrinth.api`)
+async fn api_base() -> String {
+	get_config().await.modrinth.api.trim_end_matches('/').to_string()
+}
+
+async fn get(client: &Client, path: &str) -> RequestBuilder {
+	client.get(format!("{}/v2{path}", api_base().await))
+		.header(USER_AGENT, MODRINTH_USER_AGENT)
+}
+
+/// What project can run on, server project is tagged by loader it's compatible with
+#[derive(Debug, Clone)]
+pub struct ModrinthTarget {
+	pub loaders: &'static [&'static str],
+	/// None if game version doesn't matter (proxy)
+	pub game_version: Option<String>,
+}
+
+impl ModrinthTarget {
+	pub fn new(mod_type: &ModType, mc_version: &str) -> Self {
+		let loaders: &'static [&'static str] = match mod_type {
+			ModType::Vanilla => { &[] }
+			ModType::Forge(_) => { &["forge"] }
+			ModType::NeoForge(_) => { &["neoforge"] }
+			ModType::Purpur => { &["purpur", "paper", "spigot", "bukkit"] }
+			ModType::Paper => { &["paper", "spigot", "bukkit"] }
+			ModType::Folia => { &["folia"] }
+			ModType::Velocity => { &["velocity"] }
+			ModType::Waterfall => { &["waterfall", "bungeecord"] }
+		};
+		Self {
+			loaders,
+			game_version: (!mod_type.is_proxy()).then(|| mc_version.to_string()),
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchResult {
+	pub hits: Vec<SearchHit>,
+	pub offset: u32,
+	pub limit: u32,
+	pub total_hits: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchHit {
+	pub project_id: String,
+	pub slug: String,
+	pub title: String,
+	#[serde(default)]
+	pub description: String,
+	#[serde(default)]
+	pub author: String,
+	#[serde(default)]
+	pub downloads: u64,
+	#[serde(default)]
+	pub icon_url: Option<String>,
+	/// Game versions
+	#[serde(default)]
+	pub versions: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModrinthVersion {
+	pub id: String,
+	pub project_id: String,
+	pub name: String,
+	pub version_number: String,
+	/// `release`, `beta` or `alpha`
+	pub version_type: String,
+	#[serde(default)]
+	pub game_versions: Vec<String>,
+	#[serde(default)]
+	pub loaders: Vec<String>,
+	pub files: Vec<ModrinthFile>,
+	#[serde(default)]
+	pub dependencies: Vec<ModrinthDependency>,
+	/// Markdown
+	#[serde(default)]
+	pub changelog: Option<String>,
+	/// RFC 3339 in UTC, so it can be compared as string
+	#[serde(default)]
+	pub date_published: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModrinthFile {
+	pub url: String,
+	pub filename: String,
+	#[serde(default)]
+	pub primary: bool,
+	pub size: u64,
+	/// `sha1` and `sha512`
+	pub hashes: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModrinthDependency {
+	pub version_id: Option<String>,
+	pub project_id: Option<String>,
+	/// `required`, `optional`, `incompatible` or `embedded`
+	pub dependency_type: String,
+}
+
+impl ModrinthVersion {
+	/// File to install (primary file or the first one)
+	pub fn primary_file(&self) -> Option<&ModrinthFile> {
+		self.files.iter().find(|it| it.primary).or_else(|| self.files.first())
+	}
+}
+
+impl ModrinthFile {
+	pub fn hash(&self) -> Option<FileHash> {
+		if let Some(it) = self.hashes.get("sha512") {
+			return Some(FileHash::Sha512(it.clone()));
+		}
+		self.hashes.get("sha1").map(|it| FileHash::Sha1(it.clone()))
+	}
+}
+
+/// Search mod (or plugin) projects compatible with target
+pub async fn search(client: &Client, query: &str, target: &ModrinthTarget, offset: u32, limit: u32) -> io::Result<SearchResult> {
+	// facets are AND of OR groups
+	let mut facets = vec![];
+	if !target.loaders.is_empty() {
+		facets.push(target.loaders.iter().map(|it| format!("categories:{it}")).collect::<Vec<_>>());
+	}
+	if let Some(version) = &target.game_version {
+		facets.push(vec![format!("versions:{version}")]);
+	}
+	let facets = serde_json::to_string(&facets)?;
+	let resp = get(client, "/search").await
+		.query(&[("query", query), ("facets", &facets), ("offset", &offset.to_string()), ("limit", &limit.to_string())])
+		.send().await.map_err(reqwest_to_io)?
+		.error_for_status().map_err(reqwest_to_io)?;
+	resp.json().await.map_err(reqwest_to_io)
+}
+
+/// Versions of project (id or slug) compatible with target, newest first
+pub async fn versions(client: &Client, project: &str, target: &ModrinthTarget) -> io::Result<Vec<ModrinthVersion>> {
+	let mut query = vec![];
+	if !target.loaders.is_empty() {
+		query.push(("loaders", serde_json::to_string(target.loaders)?));
+	}
+	if let Some(version) = &target.game_version {
+		query.push(("game_versions", serde_json::to_string(&[version])?));
+	}
+	let resp = get(client, &format!("/project/{project}/version")).await
+		.query(&query)
+		.send().await.map_err(reqwest_to_io)?;
+	if resp.status().as_u16() == 404 {
+		return Err(io::Error::new(ErrorKind::NotFound, format!("Modrinth project {project} was not found")));
+	}
+	resp.error_for_status().map_err(reqwest_to_io)?.json().await.map_err(reqwest_to_io)
+}
+
+pub async fn version(client: &Client, id: &str) -> io::Result<ModrinthVersion> {
+	let resp = get(client, &format!("/version/{id}")).await
+		.send().await.map_err(reqwest_to_io)?;
+	if resp.status().as_u16() == 404 {
+		return Err(io::Error::new(ErrorKind::NotFound, format!("Modrinth version {id} was not found")));
+	}
+	resp.error_for_status().map_err(reqwest_to_io)?.json().await.map_err(reqwest_to_io)
+}
+
+/// Pick version (latest compatible one if not specified) and its required dependencies.
+/// Projects in `installed` are skipped
+pub async fn resolve(client: &Client,
+                     project: &str,
+                     version_id: Option<&str>,
+                     target: &ModrinthTarget,
+                     installed: &HashSet<String>) -> io::Result<Vec<ModrinthVersion>> {
+	let first = match version_id {
+		Some(id) => { version(client, id).await? }
+		None => { latest(client, project, target).await? }
+	};
+	let mut seen: HashSet<String> = installed.clone();
+	seen.insert(first.project_id.clone());
+	let mut queue = vec![first];
+	let mut resolved = vec![];
+	while let Some(current) = queue.pop() {
+		for dep in current.dependencies.iter().filter(|it| it.dependency_type == "required") {
+			if dep.project_id.as_ref().is_some_and(|it| seen.contains(it)) {
+				continue;
+			}
+			let dep_version = match (&dep.version_id, &dep.project_id) {
+				(Some(id), _) => { version(client, id).await? }
+				(None, Some(project)) => { latest(client, project, target).await? }
+				(None, None) => { continue; }
+			};
+			if seen.insert(dep_version.project_id.clone()) {
+				debug!("{} requires {}", current.name, dep_version.name);
+				queue.push(dep_version);
+			}
+		}
+		resolved.push(current);
+	}
+	Ok(resolved)
+}
+
+/// Newest compatible version if it's published after installed version `current`, None if it's up to date
+pub async fn newer(client: &Client, project: &str, current: &str, target: &ModrinthTarget) -> io::Result<Option<ModrinthVersion>> {
+	let mut versions = versions(client, project, target).await?;
+	versions.sort_by(|a, b| b.date_published.cmp(&a.date_published));
+	let Some(latest) = versions.first() else {
+		return Err(io::Error::new(ErrorKind::NotFound, format!("{project} has no version for this server")));
+	};
+	if latest.id == current {
+		return Ok(None);
+	}
+	let published = match versions.iter().find(|it| it.id == current) {
+		Some(it) => { it.date_published.clone() }
+		// installed version isn't compatible with target anymore
+		None => { version(client, current).await?.date_published }
+	};
+	let is_newer = latest.date_published > published;
+	Ok(is_newer.then(|| versions.swap_remove(0)))
+}
+
+pub async fn latest(client: &Client, project: &str, target: &ModrinthTarget) -> io::Result<ModrinthVersion> {
+	versions(client, project, target).await?
+		.into_iter()
+		.next()
+		.ok_or_else(|| io::Error::new(ErrorKind::NotFound, format!("{project} has no version for this server")))
+}
+
+/// Download primary file of every version into folder, return parsed mods linked to their modrinth version
+pub async fn install(client: &Client, versions: &[ModrinthVersion], folder: &Path, progress: &dyn DownloadProgress) -> Result<Vec<MinecraftMod>> {
+	let mut installed = vec![];
+	for version in versions {
+		let file = version.primary_file()
+			.ok_or_else(|| io::Error::new(ErrorKind::NotFound, format!("{} doesn't have any file", version.name)))?;
+		// don't trust file name from remote
+		let file_name = Path::new(&file.filename).file_name()
+			.ok_or_else(|| io::Error::new(ErrorKind::InvalidData, format!("Invalid file name {}", file.filename)))?;
+		progress.set_phase(&format!("downloading {}", file.filename));
+		let path = artifact_cache::fetch(client, &file.url, folder.join(file_name), file.hash().as_ref(), progress).await?;
+		let mut mc_mod = MinecraftMod::try_parse(&path).await?;
+		mc_mod.source = Some(ModSource::Modrinth {
+			project_id: version.project_id.clone(),
+			version_id: version.id.clone(),
+		});
+		installed.push(mc_mod);
+	}
+	Ok(installed)
+}
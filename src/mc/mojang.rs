@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::io::ErrorKind;
+
+use serde::Deserialize;
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+use crate::util::errors::reqwest_to_io;
+use crate::util::http::shared_client;
+use crate::util::time::timestamp_minute;
+
+static MOJANG_MANIFEST: &str = "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+
+/// Time in minutes before manifest is fetched again, it's also fetched when version is missing
+static MANIFEST_TTL: u64 = 60;
+
+/// (fetched at in minutes, manifest)
+static MANIFEST: RwLock<Option<(u64, VersionManifest)>> = RwLock::const_new(None);
+/// Minecraft version -> java major version
+static JAVA_VERSIONS: RwLock<BTreeMap<String, u8>> = RwLock::const_new(BTreeMap::new());
+
+#[derive(Deserialize, Clone)]
+struct VersionManifest {
+	versions: Vec<ManifestVersion>,
+}
+
+#[derive(Deserialize, Clone)]
+struct ManifestVersion {
+	id: String,
+	/// `release`, `snapshot`, `old_beta` or `old_alpha`
+	#[serde(rename = "type")]
+	typ: String,
+	url: String,
+}
+
+#[derive(Deserialize)]
+struct VersionDetail {
+	#[serde(rename = "javaVersion")]
+	java_version: Option<JavaVersion>,
+}
+
+#[derive(Deserialize)]
+struct JavaVersion {
+	#[serde(rename = "majorVersion")]
+	major_version: u8,
+}
+
+/// Cached manifest unless it's stale or `force` is true
+async fn manifest(force: bool) -> io::Result<VersionManifest> {
+	if let Some((fetched_at, manifest)) = MANIFEST.read().await.as_ref() {
+		if !force && timestamp_minute().saturating_sub(*fetched_at) < MANIFEST_TTL {
+			return Ok(manifest.clone());
+		}
+	}
+	debug!("fetching minecraft version manifest");
+	let resp = shared_client()?.get(MOJANG_MANIFEST).send().await
+		.and_then(|it| it.error_for_status())
+		.map_err(reqwest_to_io)?;
+	let manifest: VersionManifest = resp.json().await.map_err(reqwest_to_io)?;
+	*MANIFEST.write().await = Some((timestamp_minute(), manifest.clone()));
+	Ok(manifest)
+}
+
+async fn find_version(mc_version: &str, force: bool) -> io::Result<Option<ManifestVersion>> {
+	Ok(manifest(force).await?.versions.into_iter().find(|it| it.id == mc_version))
+}
+
+/// Release versions of minecraft, newest first as listed in manifest
+pub async fn releases(force: bool) -> io::Result<Vec<String>> {
+	Ok(manifest(force).await?.versions.into_iter()
+		.filter(|it| it.typ == "release")
+		.map(|it| it.id)
+		.collect())
+}
+
+async fn fetch_java_major(mc_version: &str) -> io::Result<Option<u8>> {
+	let version = match find_version(mc_version, false).await? {
+		Some(it) => { Some(it) }
+		// released after manifest was fetched
+		None => { find_version(mc_version, true).await? }
+	};
+	let version = version
+		.ok_or_else(|| io::Error::new(ErrorKind::NotFound, format!("minecraft {mc_version} is not in manifest")))?;
+	let resp = shared_client()?.get(&version.url).send().await
+		.and_then(|it| it.error_for_status())
+		.map_err(reqwest_to_io)?;
+	let detail: VersionDetail = resp.json().await.map_err(reqwest_to_io)?;
+	Ok(detail.java_version.map(|it| it.major_version))
+}
+
+/// Java major version required by minecraft version according to mojang (`javaVersion.majorVersion`),
+/// None if version is unknown, too old to have this field or mojang is unreachable
+pub async fn java_major(mc_version: &str) -> Option<u8> {
+	if let Some(major) = JAVA_VERSIONS.read().await.get(mc_version) {
+		return Some(*major);
+	}
+	match fetch_java_major(mc_version).await {
+		Ok(Some(major)) => {
+			JAVA_VERSIONS.write().await.insert(mc_version.to_string(), major);
+			Some(major)
+		}
+		Ok(None) => { None }
+		Err(err) => {
+			warn!("Can't get java version for minecraft {mc_version} from mojang: {err}");
+			None
+		}
+	}
+}
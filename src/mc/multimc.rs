@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::instance::mc_instance::{McInstance, ModType};
+use crate::mc::mc_config::ZipEntry;
+use crate::web::ManifestKey;
+
+/// Where bundled client is placed inside `.minecraft`
+static CLIENT_FOLDER: &str = "mmcupdater";
+
+/// Entry of `mmc-pack.json`
+#[derive(Serialize, Debug, Clone)]
+pub struct Component {
+	pub uid: &'static str,
+	pub version: String,
+	#[serde(skip_serializing_if = "std::ops::Not::not")]
+	pub important: bool,
+}
+
+/// mmcupdater client bundled into exported instance, it runs before game is launched
+pub struct UpdaterClient {
+	pub jar: PathBuf,
+	/// Url of this server
+	pub server: String,
+}
+
+/// Launcher components of instance, None if it can't be played from launcher (plugin server or proxy)
+pub fn components(mod_type: &ModType, mc_version: &str) -> Option<Vec<Component>> {
+	let mut components = vec![Component { uid: "net.minecraft", version: mc_version.to_string(), important: true }];
+	match mod_type {
+		ModType::Vanilla => {}
+		ModType::Forge(ver) => {
+			components.push(Component { uid: "net.minecraftforge", version: ver.clone(), important: false });
+		}
+		ModType::NeoForge(ver) => {
+			components.push(Component { uid: "net.neoforged", version: ver.clone(), important: false });
+		}
+		_ => { return None; }
+	}
+	Some(components)
+}
+
+/// Entries of MultiMC/Prism instance zip, distributed files are placed in `.minecraft`.
+/// None if instance can't be played from launcher
+pub async fn instance_entries(instance: &McInstance, client: Option<UpdaterClient>) -> Result<Option<BTreeMap<String, ZipEntry>>> {
+	let Some(components) = components(&instance.mod_type, instance.version()) else {
+		return Ok(None);
+	};
+	let mut entries: BTreeMap<String, ZipEntry> = instance.config.dist_files().await?
+		.into_iter()
+		.map(|(name, path)| (format!(".minecraft/{name}"), ZipEntry::File(path)))
+		.collect();
+
+	let mut cfg = format!("InstanceType=OneSix\nname={}\niconKey=default\n", instance.name);
+	if let Some(client) = client {
+		cfg.push_str(&format!("OverrideCommands=true\nPreLaunchCommand=\"$INST_JAVA\" -jar \"$INST_MC_DIR/{CLIENT_FOLDER}/mmcupdater.jar\"\n"));
+		let config = json!({
+			"server": client.server,
+			"instance": instance.name,
+			"fingerprint": ManifestKey::get().fingerprint,
+		});
+		entries.insert(format!(".minecraft/{CLIENT_FOLDER}/mmcupdater.jar"), ZipEntry::File(client.jar));
+		entries.insert(format!(".minecraft/{CLIENT_FOLDER}/config.json"), ZipEntry::Data(serde_json::to_vec_pretty(&config)?));
+	}
+	entries.insert("instance.cfg".to_string(), ZipEntry::Data(cfg.into_bytes()));
+	let pack = json!({
+		"formatVersion": 1,
+		"components": components,
+	});
+	entries.insert("mmc-pack.json".to_string(), ZipEntry::Data(serde_json::to_vec_pretty(&pack)?));
+	Ok(Some(entries))
+}
+
+#[cfg(test)]
+mod test {
+	use crate::instance::mc_instance::ModType;
+	use crate::mc::multimc::components;
+
+	#[test]
+	fn test_components() {
+		let forge = components(&ModType::Forge("47.2.0".to_string()), "1.20.1").unwrap();
+		assert_eq!(forge.iter().map(|it| (it.uid, it.version.as_str())).collect::<Vec<_>>(),
+		           vec![("net.minecraft", "1.20.1"), ("net.minecraftforge", "47.2.0")]);
+		assert_eq!(components(&ModType::Vanilla, "1.20.1").unwrap().len(), 1);
+		assert!(components(&ModType::Paper, "1.20.1").is_none());
+	}
+}
@@ -0,0 +1,89 @@
+use std::io;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use hashbrown::HashMap;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::util::errors::reqwest_to_io;
+use crate::util::artifact_cache;
+use crate::util::http::{DownloadProgress, FileHash};
+
+static PAPERMC_API: &str = "https://api.papermc.io/v2/projects";
+
+#[derive(Deserialize)]
+struct PaperProject {
+	versions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PaperBuilds {
+	builds: Vec<PaperBuild>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PaperBuild {
+	pub build: u32,
+	/// `default` or `experimental`
+	pub channel: String,
+	pub downloads: HashMap<String, PaperDownload>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PaperDownload {
+	pub name: String,
+	pub sha256: String,
+}
+
+impl PaperBuild {
+	pub fn application(&self) -> Option<&PaperDownload> {
+		self.downloads.get("application")
+	}
+}
+
+/// List every version of project (oldest first)
+/// project: `paper`, `folia`, `velocity` or `waterfall`
+pub async fn versions(client: &Client, project: &str) -> io::Result<Vec<String>> {
+	let resp = client.get(format!("{PAPERMC_API}/{project}"))
+		.send().await.map_err(reqwest_to_io)?;
+	if !resp.status().is_success() {
+		return Err(io::Error::new(ErrorKind::NotFound, format!("{project} was not found")));
+	}
+	let project: PaperProject = resp.json().await.map_err(reqwest_to_io)?;
+	Ok(project.versions)
+}
+
+/// List every build of project version (oldest first)
+pub async fn builds(client: &Client, project: &str, version: &str) -> io::Result<Vec<PaperBuild>> {
+	let resp = client.get(format!("{PAPERMC_API}/{project}/versions/{version}/builds"))
+		.send().await.map_err(reqwest_to_io)?;
+	if !resp.status().is_success() {
+		return Err(io::Error::new(ErrorKind::NotFound, format!("{project} {version} was not found")));
+	}
+	let builds: PaperBuilds = resp.json().await.map_err(reqwest_to_io)?;
+	Ok(builds.builds)
+}
+
+/// Latest build from `default` channel, or latest experimental build if version never got stable build
+pub async fn latest_build(client: &Client, project: &str, version: &str) -> io::Result<PaperBuild> {
+	let mut builds = builds(client, project, version).await?;
+	let stable = builds.iter().rposition(|it| it.channel == "default");
+	let build = match stable {
+		Some(idx) => { Some(builds.swap_remove(idx)) }
+		None => { builds.pop() }
+	};
+	build.ok_or_else(|| io::Error::new(ErrorKind::NotFound, format!("{project} {version} has no build")))
+}
+
+/// Download latest build of project version to target and verify it using sha256 from api
+pub async fn download(client: &Client, project: &str, version: &str, target: impl AsRef<Path>, progress: &dyn DownloadProgress) -> io::Result<PathBuf> {
+	let build = latest_build(client, project, version).await?;
+	let app = build.application()
+		.ok_or_else(|| io::Error::new(ErrorKind::NotFound, "Build doesn't have application download"))?;
+	debug!("downloading {project} {version} build {}", build.build);
+	let url = format!("{PAPERMC_API}/{project}/versions/{version}/builds/{}/downloads/{}", build.build, app.name);
+	let expected = FileHash::Sha256(app.sha256.clone());
+	artifact_cache::fetch(client, &url, target, Some(&expected), progress).await
+}
@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tokio::fs::{create_dir_all, read, write};
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+use crate::instance::mc_instance::{ModType, ModVersion};
+use crate::util::http::shared_client;
+use crate::util::time::timestamp_minute;
+
+static VERSION_CACHE_DIR: &str = "cache/versions";
+/// Time in minutes before version list is fetched again
+static VERSION_TTL: u64 = 60;
+
+static VERSIONS: RwLock<BTreeMap<&'static str, CachedVersion>> = RwLock::const_new(BTreeMap::new());
+
+#[derive(Serialize, Deserialize, Clone)]
+struct CachedVersion {
+	/// timestamp in minutes
+	fetched_at: u64,
+	versions: ModVersion,
+}
+
+impl CachedVersion {
+	fn is_fresh(&self) -> bool {
+		timestamp_minute().saturating_sub(self.fetched_at) < VERSION_TTL
+	}
+}
+
+fn cache_file(loader: &str) -> PathBuf {
+	PathBuf::from(VERSION_CACHE_DIR).join(format!("{loader}.json"))
+}
+
+async fn load_from_disk(loader: &str) -> Option<CachedVersion> {
+	let data = read(cache_file(loader)).await.ok()?;
+	serde_json::from_slice(&data).ok()
+}
+
+async fn save_to_disk(loader: &str, cached: &CachedVersion) -> io::Result<()> {
+	create_dir_all(VERSION_CACHE_DIR).await?;
+	let data = serde_json::to_vec(cached)?;
+	write(cache_file(loader), data).await
+}
+
+/// Get version list of loader, it will only fetch from remote if cached list is expired.
+/// When remote is unreachable last known list is used (even if it's expired)
+pub async fn versions(mod_type: &ModType) -> io::Result<ModVersion> {
+	versions_impl(mod_type, false).await
+}
+
+/// Same as [versions] but ignore ttl
+pub async fn refresh(mod_type: &ModType) -> io::Result<ModVersion> {
+	versions_impl(mod_type, true).await
+}
+
+async fn versions_impl(mod_type: &ModType, force: bool) -> io::Result<ModVersion> {
+	let loader = mod_type.loader_name();
+	let mut stale = VERSIONS.read().await.get(loader).cloned();
+	if stale.is_none() {
+		if let Some(cached) = load_from_disk(loader).await {
+			VERSIONS.write().await.insert(loader, cached.clone());
+			stale = Some(cached);
+		}
+	}
+	if let (Some(cached), false) = (&stale, force) {
+		if cached.is_fresh() {
+			return Ok(cached.versions.clone());
+		}
+	}
+
+	debug!("fetching {loader} versions");
+	match mod_type.versions(&shared_client()?).await {
+		Ok(versions) => {
+			let cached = CachedVersion { fetched_at: timestamp_minute(), versions };
+			if let Err(err) = save_to_disk(loader, &cached).await {
+				warn!("Failed to persist {loader} versions: {err}");
+			}
+			let versions = cached.versions.clone();
+			VERSIONS.write().await.insert(loader, cached);
+			Ok(versions)
+		}
+		Err(err) => {
+			match stale {
+				Some(cached) => {
+					warn!("Failed to fetch {loader} versions ({err}); using cached list");
+					Ok(cached.versions)
+				}
+				None => { Err(err) }
+			}
+		}
+	}
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs::{copy, create_dir_all, metadata, read, remove_file, rename, write};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::util::config::get_config;
+use crate::util::fs::sha256;
+use crate::util::http::{download_verified, DownloadProgress, FileHash, verify_file};
+
+/// Copy this folder to seed cache of another host
+static ARTIFACT_DIR: &str = "cache/artifacts";
+
+/// One lock per url (sha256 of url), concurrent fetch of the same url wait for a single download
+/// instead of writing into the same `.part` file
+static FETCHING: Mutex<BTreeMap<String, Arc<Mutex<()>>>> = Mutex::const_new(BTreeMap::new());
+
+/// Content of `urls/<sha256 of url>.json`; file itself is stored at `blobs/<sha256 of content>`
+#[derive(Serialize, Deserialize)]
+struct UrlEntry {
+	url: String,
+	sha256: String,
+}
+
+fn hex_sha256(data: &[u8]) -> String {
+	String::from_iter(Sha256::digest(data).iter().map(|it| format!("{:02x}", it)))
+}
+
+fn blob_path(sha256: &str) -> PathBuf {
+	Path::new(ARTIFACT_DIR).join("blobs").join(sha256)
+}
+
+fn url_path(url: &str) -> PathBuf {
+	Path::new(ARTIFACT_DIR).join("urls").join(format!("{}.json", hex_sha256(url.as_bytes())))
+}
+
+/// Replace url prefix using mirror table from config (first match win)
+pub async fn rewrite(url: &str) -> String {
+	let config = get_config().await;
+	for mirror in &config.artifacts.mirrors {
+		if let Some(rest) = url.strip_prefix(mirror.from.as_str()) {
+			return format!("{}{rest}", mirror.to);
+		}
+	}
+	url.to_string()
+}
+
+/// Find cached file of url, it's also found by content if expected sha256 is known.
+/// File at url may be changed, so it's only used when expected hash is known
+async fn lookup(url: &str, expected: Option<&FileHash>) -> Option<PathBuf> {
+	let expected = expected?;
+	if let FileHash::Sha256(hash) = expected {
+		let blob = blob_path(&hash.to_ascii_lowercase());
+		if metadata(&blob).await.is_ok() {
+			return Some(blob);
+		}
+	}
+	let entry: UrlEntry = serde_json::from_slice(&read(url_path(url)).await.ok()?).ok()?;
+	let blob = blob_path(&entry.sha256);
+	metadata(&blob).await.ok()?;
+	match expected {
+		FileHash::Sha256(hash) => {
+			// file at this url has been changed
+			hash.eq_ignore_ascii_case(&entry.sha256).then_some(blob)
+		}
+		hash => {
+			verify_file(&blob, hash).await.unwrap_or(false).then_some(blob)
+		}
+	}
+}
+
+async fn save_entry(url: &str, entry: &UrlEntry) -> io::Result<()> {
+	let path = url_path(url);
+	if let Some(parent) = path.parent() {
+		create_dir_all(parent).await?;
+	}
+	write(path, serde_json::to_vec(entry)?).await
+}
+
+/// Copy cached file to target; it isn't linked, otherwise writing to instance file would change the cache too
+async fn place(blob: &Path, target: &Path) -> io::Result<()> {
+	if let Some(parent) = target.parent() {
+		create_dir_all(parent).await?;
+	}
+	remove_file(target).await.ok();
+	copy(blob, target).await?;
+	Ok(())
+}
+
+/// Download file (target must be file path) through shared cache, so the same artifact is downloaded only once.
+/// Url is rewritten by mirror table before downloading but cache is still keyed by original url
+pub async fn fetch(client: &Client,
+                   url: &str,
+                   target: impl AsRef<Path>,
+                   expected: Option<&FileHash>,
+                   progress: &dyn DownloadProgress) -> io::Result<PathBuf> {
+	let target = target.as_ref();
+	let source = rewrite(url).await;
+	if !get_config().await.artifacts.cache {
+		let (out, _) = download_verified(client, &source, target, expected, progress).await?;
+		return Ok(out);
+	}
+	let key = hex_sha256(url.as_bytes());
+	let lock = Arc::clone(FETCHING.lock().await.entry(key.clone()).or_default());
+	let result = {
+		let _guard = lock.lock().await;
+		fetch_cached(client, url, &source, &key, target, expected, progress).await
+	};
+	let mut fetching = FETCHING.lock().await;
+	// nobody else is waiting for it
+	if Arc::strong_count(&lock) == 2 {
+		fetching.remove(&key);
+	}
+	result
+}
+
+/// Body of [fetch], caller must hold lock of url
+async fn fetch_cached(client: &Client,
+                      url: &str,
+                      source: &str,
+                      key: &str,
+                      target: &Path,
+                      expected: Option<&FileHash>,
+                      progress: &dyn DownloadProgress) -> io::Result<PathBuf> {
+	if let Some(blob) = lookup(url, expected).await {
+		debug!("using cached {url} from {blob:?}");
+		place(&blob, target).await?;
+		let len = metadata(&blob).await?.len();
+		progress.on_progress(len, Some(len));
+		return Ok(target.to_path_buf());
+	}
+
+	let tmp = Path::new(ARTIFACT_DIR).join("tmp").join(key);
+	let (file, hash) = download_verified(client, source, &tmp, expected, progress).await?;
+	let content_hash = match expected {
+		None | Some(FileHash::Sha256(_)) => { hash.to_ascii_lowercase() }
+		Some(_) => { sha256(&file).await? }
+	};
+	let blob = blob_path(&content_hash);
+	if let Some(parent) = blob.parent() {
+		create_dir_all(parent).await?;
+	}
+	rename(&file, &blob).await?;
+	// unverified file is still found by its sha256
+	if expected.is_some() {
+		let entry = UrlEntry { url: url.to_string(), sha256: content_hash };
+		if let Err(err) = save_entry(url, &entry).await {
+			warn!("Failed to index cached {url}: {err}");
+		}
+	}
+	place(&blob, target).await?;
+	Ok(target.to_path_buf())
+}
\ No newline at end of file
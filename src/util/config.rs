@@ -12,6 +12,7 @@ use tower_http::cors::{AllowHeaders, CorsLayer};
 use tracing::{error, info};
 
 use crate::util::fs::create_if_not_existed;
+use crate::util::java::JavaProviderKind;
 
 static DEFAULT_CONFIG_YML: &str = include_str!("../resources/dummy_config.yml");
 static CONFIG: RwLock<ConfigRoot> = RwLock::const_new(ConfigRoot::const_default());
@@ -24,6 +25,22 @@ pub struct ConfigRoot {
 	pub monitor: MonitorConfig,
 	#[serde(default)]
 	pub security: Security,
+	#[serde(default)]
+	pub java: JavaConfig,
+	#[serde(default)]
+	pub artifacts: ArtifactConfig,
+	#[serde(default)]
+	pub modrinth: ModrinthConfig,
+	#[serde(default)]
+	pub curseforge: CurseForgeConfig,
+	#[serde(default)]
+	pub mod_updates: ModUpdateConfig,
+	#[serde(default)]
+	pub manifest_signing: ManifestSigningConfig,
+	#[serde(default)]
+	pub export: ExportConfig,
+	#[serde(default)]
+	pub import: ImportConfig,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -158,6 +175,156 @@ const fn default_max_login_retry() -> i32 { 15 }
 
 const fn default_login_cool_down() -> u64 { 30 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JavaConfig {
+	/// Where to download missing java runtime from
+	#[serde(default)]
+	pub provider: JavaProviderKind,
+	/// Github api root (used by graalvm provider); change it to use mirror
+	#[serde(default = "default_github_api")]
+	pub github_api: Cow<'static, str>,
+	/// Adoptium api root (used by adoptium provider); change it to use mirror
+	#[serde(default = "default_adoptium_api")]
+	pub adoptium_api: Cow<'static, str>,
+}
+
+impl Default for JavaConfig {
+	fn default() -> Self {
+		Self {
+			provider: JavaProviderKind::GraalVm,
+			github_api: default_github_api(),
+			adoptium_api: default_adoptium_api(),
+		}
+	}
+}
+
+const fn default_github_api() -> Cow<'static, str> { Cow::Borrowed("https://api.github.com") }
+
+const fn default_adoptium_api() -> Cow<'static, str> { Cow::Borrowed("https://api.adoptium.net") }
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ArtifactConfig {
+	/// Keep downloaded server jars, installers and java archives in `cache/artifacts`
+	#[serde(default = "default_artifact_cache")]
+	pub cache: bool,
+	/// Url prefix rewrite applied before downloading artifact
+	#[serde(default)]
+	pub mirrors: Vec<Mirror>,
+}
+
+impl Default for ArtifactConfig {
+	fn default() -> Self {
+		Self {
+			cache: default_artifact_cache(),
+			mirrors: Vec::new(),
+		}
+	}
+}
+
+const fn default_artifact_cache() -> bool { true }
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mirror {
+	/// Original url prefix eg. `https://maven.minecraftforge.net`
+	pub from: String,
+	/// Replacement eg. `http://mirror.lan/forge`
+	pub to: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ModrinthConfig {
+	/// Modrinth api root; change it to use mirror or local stub
+	#[serde(default = "default_modrinth_api")]
+	pub api: Cow<'static, str>,
+}
+
+impl Default for ModrinthConfig {
+	fn default() -> Self {
+		Self {
+			api: default_modrinth_api(),
+		}
+	}
+}
+
+const fn default_modrinth_api() -> Cow<'static, str> { Cow::Borrowed("https://api.modrinth.com") }
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CurseForgeConfig {
+	/// CurseForge core api root
+	#[serde(default = "default_curseforge_api")]
+	pub api: Cow<'static, str>,
+	/// Api key from <https://console.curseforge.com>, curseforge is disabled if it's empty
+	#[serde(default)]
+	pub api_key: String,
+}
+
+impl Default for CurseForgeConfig {
+	fn default() -> Self {
+		Self {
+			api: default_curseforge_api(),
+			api_key: String::new(),
+		}
+	}
+}
+
+const fn default_curseforge_api() -> Cow<'static, str> { Cow::Borrowed("https://api.curseforge.com") }
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ModUpdateConfig {
+	/// Hours between update check of mods installed from modrinth or curseforge; 0 to disable
+	#[serde(default = "default_update_interval")]
+	pub check_interval: u64,
+	/// Number of replaced mod files kept per instance for rollback
+	#[serde(default = "default_history_size")]
+	pub history_size: usize,
+}
+
+impl Default for ModUpdateConfig {
+	fn default() -> Self {
+		Self {
+			check_interval: default_update_interval(),
+			history_size: default_history_size(),
+		}
+	}
+}
+
+const fn default_update_interval() -> u64 { 12 }
+
+const fn default_history_size() -> usize { 32 }
+
+/// Ed25519 key pair used to sign client manifest, it's generated if both are missing
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ManifestSigningConfig {
+	/// Path to private key (pkcs8 pem), `manifest.key` if empty
+	#[serde(default)]
+	pub private_key: String,
+	/// Path to public key (pem), `manifest.pub` if empty
+	#[serde(default)]
+	pub public_key: String,
+}
+
+/// Instance export for launcher (MultiMC/Prism)
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ExportConfig {
+	/// Url client uses to reach this server, taken from `Host` header of request if empty
+	#[serde(default)]
+	pub public_url: String,
+	/// Path to mmcupdater client jar, it can be bundled into exported instance if it's set
+	#[serde(default)]
+	pub client_jar: String,
+}
+
+/// Import of existing server folder from http api (cli isn't restricted)
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ImportConfig {
+	/// Folders that server can be imported from (only folders inside them), import is disabled if it's empty
+	#[serde(default)]
+	pub roots: Vec<String>,
+	/// Allow `move` mode, otherwise folder can only be symlinked
+	#[serde(default)]
+	pub allow_move: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Cors {
 	/// list of allowed methods send by cors header
@@ -232,6 +399,38 @@ impl ConfigRoot {
 				max_login_retry: default_max_login_retry(),
 				login_cool_down: default_login_cool_down(),
 			},
+			java: JavaConfig {
+				provider: JavaProviderKind::GraalVm,
+				github_api: default_github_api(),
+				adoptium_api: default_adoptium_api(),
+			},
+			artifacts: ArtifactConfig {
+				cache: default_artifact_cache(),
+				mirrors: Vec::new(),
+			},
+			modrinth: ModrinthConfig {
+				api: default_modrinth_api(),
+			},
+			curseforge: CurseForgeConfig {
+				api: default_curseforge_api(),
+				api_key: String::new(),
+			},
+			mod_updates: ModUpdateConfig {
+				check_interval: default_update_interval(),
+				history_size: default_history_size(),
+			},
+			manifest_signing: ManifestSigningConfig {
+				private_key: String::new(),
+				public_key: String::new(),
+			},
+			export: ExportConfig {
+				public_url: String::new(),
+				client_jar: String::new(),
+			},
+			import: ImportConfig {
+				roots: Vec::new(),
+				allow_move: false,
+			},
 		}
 	}
 }
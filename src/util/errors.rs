@@ -61,9 +61,9 @@ pub mod rest {
 		Ok(StatusCode::CONFLICT.into_response())
 	}
 
-	pub fn created<T: Serialize>(data: T) -> Resp {
+	pub fn accepted<T: Serialize>(data: T) -> Resp {
 		let mut res = ResultBase::success(data).into_response();
-		*res.status_mut() = StatusCode::CREATED;
+		*res.status_mut() = StatusCode::ACCEPTED;
 		Ok(res)
 	}
 
@@ -1,13 +1,19 @@
 use serde::Deserialize;
 
+use crate::util::config::get_config;
 use crate::util::errors::ErrorWrapper;
 use crate::util::http::new_client;
 
+/// github api root from config (`java.github_api`)
+async fn api_base() -> String {
+	get_config().await.java.github_api.trim_end_matches('/').to_string()
+}
+
 /// fetch latest release from github repo  
 /// repo: <OWNER>/<REPO>
 pub async fn get_gh_latest_release(repo: &str) -> Result<Option<GhRelease>, ErrorWrapper> {
 	let resp: Option<GhRelease> = new_client()?
-		.get(format!("https://api.github.com/repos/{repo}/releases/latest"))
+		.get(format!("{}/repos/{repo}/releases/latest", api_base().await))
 		.send()
 		.await?
 		.json()
@@ -19,9 +25,22 @@ pub async fn get_gh_latest_release(repo: &str) -> Result<Option<GhRelease>, Erro
 /// tag: Release tag from github (can be found at <REPO_URL>/tags)
 pub async fn get_gh_release_from_tag(repo: &str,tag:&str) -> Result<Option<GhRelease>, ErrorWrapper> {
 	let resp: Option<GhRelease> = new_client()?
-		.get(format!("https://api.github.com/repos/{repo}/releases/tags/{tag}"))
+		.get(format!("{}/repos/{repo}/releases/tags/{tag}", api_base().await))
+		.send()
+		.await?
+		.json()
+		.await?;
+	Ok(resp)
+}
+
+/// fetch recent releases (newest first) from github repo
+/// repo: <OWNER>/<REPO>
+pub async fn get_gh_releases(repo: &str) -> Result<Vec<GhRelease>, ErrorWrapper> {
+	let resp: Vec<GhRelease> = new_client()?
+		.get(format!("{}/repos/{repo}/releases?per_page=100", api_base().await))
 		.send()
 		.await?
+		.error_for_status()?
 		.json()
 		.await?;
 	Ok(resp)
@@ -0,0 +1,54 @@
+/// Match path separated by `/` against glob. `*` and `?` don't cross `/` but `**` does.
+/// Pattern without `/` is matched against file name only (like `.gitignore`)
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+	let pattern: Vec<char> = pattern.trim_start_matches('/').chars().collect();
+	if !pattern.contains(&'/') {
+		let name = path.rsplit('/').next().unwrap_or(path);
+		return match_chars(&pattern, &name.chars().collect::<Vec<_>>());
+	}
+	match_chars(&pattern, &path.chars().collect::<Vec<_>>())
+}
+
+fn match_chars(pattern: &[char], text: &[char]) -> bool {
+	match pattern {
+		[] => { text.is_empty() }
+		['*', '*', rest @ ..] => {
+			// `a/**/b` matches `a/b` too
+			if let ['/', after @ ..] = rest {
+				if match_chars(after, text) {
+					return true;
+				}
+			}
+			(0..=text.len()).any(|i| match_chars(rest, &text[i..]))
+		}
+		['*', rest @ ..] => {
+			let segment = text.iter().position(|it| *it == '/').unwrap_or(text.len());
+			(0..=segment).any(|i| match_chars(rest, &text[i..]))
+		}
+		['?', rest @ ..] => {
+			matches!(text.first(), Some(c) if *c != '/') && match_chars(rest, &text[1..])
+		}
+		[c, rest @ ..] => {
+			text.first() == Some(c) && match_chars(rest, &text[1..])
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::util::glob::glob_match;
+
+	#[test]
+	fn test_glob() {
+		assert!(glob_match("*.jar", "mods/jei.jar"));
+		assert!(!glob_match("*.jar", "mods/jei.jar.disabled"));
+		assert!(glob_match("mods/*.jar", "mods/jei.jar"));
+		assert!(!glob_match("mods/*.jar", "mods/sub/jei.jar"));
+		assert!(glob_match("mods/**/*.jar", "mods/jei.jar"));
+		assert!(glob_match("mods/**/*.jar", "mods/sub/dir/jei.jar"));
+		assert!(glob_match("config/**", "config/a/b.toml"));
+		assert!(glob_match("/config/?.toml", "config/a.toml"));
+		assert!(!glob_match("config/?.toml", "config/ab.toml"));
+		assert!(glob_match("*-server-*", "mods/spark-server-1.0.jar"));
+	}
+}
@@ -1,28 +1,121 @@
-use std::io::Result;
+use std::io;
+use std::io::{ErrorKind, Result};
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 use bytes::Bytes;
 use futures::StreamExt;
-use reqwest::{Client, ClientBuilder};
-use reqwest::header::{CONTENT_DISPOSITION, HeaderValue};
+use reqwest::{Client, ClientBuilder, Response, StatusCode};
+use reqwest::header::{CONTENT_DISPOSITION, ETAG, HeaderMap, HeaderValue, IF_RANGE, LAST_MODIFIED, RANGE};
 use reqwest::redirect::Policy;
-use sha2::{Digest, Sha256};
-use tokio::fs::metadata;
-use tokio::io::AsyncWriteExt;
-use tracing::{debug, trace};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use tokio::fs::{File, metadata, OpenOptions, read_to_string, remove_file, rename, write};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{debug, error, trace};
 
 use crate::util::errors::reqwest_to_io;
 use crate::util::fs::new_file;
 
-// return (DownloadedLocation, Sha256)
-pub async fn download_to(client: &Client, url: &str, target: impl AsRef<Path>) -> Result<(PathBuf, String)> {
-	let target = target.as_ref();
-	debug!("downloading {url:?} to {target:?}");
-	let resp = client.get(url).send().await.map_err(reqwest_to_io)?;
-	let headers = resp.headers();
-	let mut sha = Sha256::default();
+/// Receive progress of running download
+pub trait DownloadProgress: Send + Sync {
+	/// total is None when server doesn't tell content length
+	fn on_progress(&self, downloaded: u64, total: Option<u64>);
+
+	/// Start new step of operation eg. `downloading server`
+	fn set_phase(&self, _phase: &str) {}
+}
+
+impl DownloadProgress for () {
+	fn on_progress(&self, _: u64, _: Option<u64>) {}
+}
+
+/// Expected hash of file in hex
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileHash {
+	/// Only when api doesn't publish any better hash (eg. purpur)
+	Md5(String),
+	Sha1(String),
+	Sha256(String),
+	Sha512(String),
+}
+
+impl FileHash {
+	pub fn hex(&self) -> &str {
+		match self {
+			FileHash::Md5(it) | FileHash::Sha1(it) | FileHash::Sha256(it) | FileHash::Sha512(it) => { it }
+		}
+	}
+
+	fn hasher(&self) -> Hasher {
+		match self {
+			FileHash::Md5(_) => { Hasher::Md5(Md5::default()) }
+			FileHash::Sha1(_) => { Hasher::Sha1(Sha1::default()) }
+			FileHash::Sha256(_) => { Hasher::Sha256(Sha256::default()) }
+			FileHash::Sha512(_) => { Hasher::Sha512(Sha512::default()) }
+		}
+	}
+}
+
+/// Take hash from checksum file, it may be followed by file name eg. `<hash>  server.jar`
+pub fn parse_checksum_file(content: &str) -> Option<&str> {
+	content.split_whitespace().next()
+}
+
+enum Hasher {
+	Md5(Md5),
+	Sha1(Sha1),
+	Sha256(Sha256),
+	Sha512(Sha512),
+}
+
+impl Hasher {
+	fn update(&mut self, data: &[u8]) {
+		match self {
+			Hasher::Md5(it) => { it.update(data) }
+			Hasher::Sha1(it) => { it.update(data) }
+			Hasher::Sha256(it) => { it.update(data) }
+			Hasher::Sha512(it) => { it.update(data) }
+		}
+	}
+
+	fn finalize(self) -> String {
+		let hash = match self {
+			Hasher::Md5(it) => { it.finalize().to_vec() }
+			Hasher::Sha1(it) => { it.finalize().to_vec() }
+			Hasher::Sha256(it) => { it.finalize().to_vec() }
+			Hasher::Sha512(it) => { it.finalize().to_vec() }
+		};
+		String::from_iter(hash.iter().map(|it| format!("{:02x}", it)))
+	}
+}
+
+/// Incomplete download of file, kept so download can be resumed
+fn part_file(out: &Path) -> PathBuf {
+	let mut name = out.file_name().map(|it| it.to_os_string()).unwrap_or_default();
+	name.push(".part");
+	out.with_file_name(name)
+}
+
+/// `ETag` or `Last-Modified` of partial download, resume is only accepted if file at url is still the same
+fn validator_file(part: &Path) -> PathBuf {
+	let mut name = part.file_name().map(|it| it.to_os_string()).unwrap_or_default();
+	name.push(".validator");
+	part.with_file_name(name)
+}
+
+/// Value for `If-Range`, weak etag can't be used for it
+fn validator(headers: &HeaderMap) -> Option<&str> {
+	headers.get(ETAG)
+		.and_then(|it| it.to_str().ok())
+		.filter(|it| !it.starts_with("W/"))
+		.or_else(|| headers.get(LAST_MODIFIED).and_then(|it| it.to_str().ok()))
+}
+
+fn file_name(headers: &HeaderMap, url: &str) -> Option<String> {
 	trace!("Getting filename..");
-	let file_name = headers.get(CONTENT_DISPOSITION)
+	headers.get(CONTENT_DISPOSITION)
 		.and_then(|it: &HeaderValue| {
 			let bytes = it.as_bytes();
 			let idx = bytes.windows(9)
@@ -30,46 +123,182 @@ pub async fn download_to(client: &Client, url: &str, target: impl AsRef<Path>) -
 			let (_, data) = bytes.split_at(idx + 9);
 			Some(data)
 		})
-		.map(|it: &[u8]| String::from_utf8_lossy(it).trim_matches(['"', ' '].as_slice()).to_string());
-	let meta = metadata(target).await;
-	let out = if meta.is_ok() && meta.as_ref().unwrap().is_dir() {
-		if let Some(name) = file_name {
-			target.join(name)
-		} else {
-			PathBuf::from(target)
+		.map(|it: &[u8]| String::from_utf8_lossy(it).trim_matches(['"', ' '].as_slice()).to_string())
+		.or_else(|| {
+			let path = url.split(['?', '#']).next()?;
+			let name = path.rsplit('/').next()?;
+			(!name.is_empty()).then(|| name.to_string())
+		})
+}
+
+async fn request(client: &Client, url: &str, offset: u64, validator: Option<&str>) -> Result<Response> {
+	let mut req = client.get(url);
+	if offset > 0 {
+		req = req.header(RANGE, format!("bytes={offset}-"));
+		if let Some(validator) = validator {
+			// server send the whole file instead if it has been changed
+			req = req.header(IF_RANGE, validator);
+		}
+	}
+	req.send().await.map_err(reqwest_to_io)
+}
+
+/// Feed existing content of partial download to hasher
+async fn hash_existing(path: &Path, mut hasher: Hasher) -> Result<Hasher> {
+	let mut f = File::open(path).await?;
+	let mut buf = vec![0u8; 8192];
+	loop {
+		let len = f.read(&mut buf).await?;
+		if len == 0 {
+			break;
 		}
+		let res = tokio_rayon::spawn(move || {
+			hasher.update(&buf[..len]);
+			(hasher, buf)
+		}).await;
+		hasher = res.0;
+		buf = res.1;
+	}
+	Ok(hasher)
+}
+
+/// Check file against expected hash
+pub async fn verify_file(path: impl AsRef<Path>, expected: &FileHash) -> Result<bool> {
+	let hasher = hash_existing(path.as_ref(), expected.hasher()).await?;
+	let hash = tokio_rayon::spawn(move || hasher.finalize()).await;
+	Ok(hash.eq_ignore_ascii_case(expected.hex()))
+}
+
+/// Download into `<file>.part` (resume it using http range if it's existed) then move it to target once it's completed.
+/// Partial file is only resumed if server confirms it's unchanged by `If-Range`, or if expected hash will catch it.
+/// If target is directory file name will come from response.
+/// When expected hash is given, file that doesn't match will be removed and returned hash use the same algorithm; otherwise it's sha256
+pub async fn download_verified(client: &Client,
+                               url: &str,
+                               target: impl AsRef<Path>,
+                               expected: Option<&FileHash>,
+                               progress: &dyn DownloadProgress) -> Result<(PathBuf, String)> {
+	let target = target.as_ref();
+	debug!("downloading {url:?} to {target:?}");
+	let is_dir = metadata(target).await.map(|it| it.is_dir()).unwrap_or(false);
+	let (out, first) = if is_dir {
+		let resp = request(client, url, 0, None).await?;
+		let name = file_name(resp.headers(), url)
+			.ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, format!("Can't find file name of {url}")))?;
+		(target.join(name), Some(resp))
 	} else {
-		PathBuf::from(target)
+		(PathBuf::from(target), None)
+	};
+	let part = part_file(&out);
+	let validator_path = validator_file(&part);
+	let stored = read_to_string(&validator_path).await.ok();
+	let mut offset = metadata(&part).await.map(|it| it.len()).unwrap_or(0);
+	if offset > 0 && stored.is_none() && expected.is_none() {
+		debug!("{part:?} can't be checked; restarting");
+		offset = 0;
+	}
+	let mut resp = match first {
+		Some(resp) if offset == 0 => { resp }
+		_ => { request(client, url, offset, stored.as_deref()).await? }
+	};
+	if resp.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+		debug!("{part:?} can't be resumed; restarting");
+		offset = 0;
+		resp = request(client, url, 0, None).await?;
+	}
+	let resp = resp.error_for_status().map_err(reqwest_to_io)?;
+	let resumed = offset > 0 && resp.status() == StatusCode::PARTIAL_CONTENT;
+
+	let mut hasher = match expected {
+		Some(hash) => { hash.hasher() }
+		None => { Hasher::Sha256(Sha256::default()) }
 	};
-	let mut file = new_file(&out).await.expect("Create file");
-	let mut data = resp.bytes_stream();
 	let mut file_len = 0u64;
+	let mut file = if resumed {
+		debug!("resuming {url} from {offset} bytes");
+		hasher = hash_existing(&part, hasher).await?;
+		file_len = offset;
+		OpenOptions::new().append(true).open(&part).await?
+	} else {
+		let file = new_file(&part).await?;
+		file.set_len(0).await?;
+		match validator(resp.headers()) {
+			Some(validator) => { write(&validator_path, validator).await?; }
+			None => { remove_file(&validator_path).await.ok(); }
+		}
+		file
+	};
+	let total = resp.content_length().map(|it| it + file_len);
+	let mut data = resp.bytes_stream();
 	while let Some(data) = data.next().await {
 		let bytes: Bytes = data.map_err(reqwest_to_io)?;
 		file.write_all(&bytes).await?;
 		file_len = file_len + (bytes.len() as u64);
-		sha = tokio_rayon::spawn(|| {
-			sha.update(bytes);
-			sha
+		progress.on_progress(file_len, total);
+		hasher = tokio_rayon::spawn(move || {
+			hasher.update(&bytes);
+			hasher
 		}).await;
 	}
-	debug!("content from {url} has been downloaded to {out:?}");
 	file.flush().await?;
-	// due replace existing file truncate its size
-	if meta?.len() != file_len {
-		file.set_len(file_len).await?;
-	}
 	file.shutdown().await?;
-	let hash = tokio_rayon::spawn(move || {
-		String::from_iter(sha.finalize()[..].iter().map(|it| format!("{:02x}", it)))
-	}).await;
+	let hash = tokio_rayon::spawn(move || hasher.finalize()).await;
+	if let Some(expected) = expected {
+		if !hash.eq_ignore_ascii_case(expected.hex()) {
+			error!("Fail to verify hash of {url} expected={} actual={hash}", expected.hex());
+			remove_file(&part).await.ok();
+			remove_file(&validator_path).await.ok();
+			return Err(io::Error::new(ErrorKind::InvalidData, "Invalid hash"));
+		}
+	}
+	rename(&part, &out).await?;
+	remove_file(&validator_path).await.ok();
+	debug!("content from {url} has been downloaded to {out:?}");
 	Ok((out, hash))
 }
 
+static SHARED_CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Client shared across whole application (cloning client is cheap, it will share connection pool)
+pub fn shared_client() -> Result<Client> {
+	if let Some(client) = SHARED_CLIENT.get() {
+		return Ok(client.clone());
+	}
+	let client = new_client()?;
+	Ok(SHARED_CLIENT.get_or_init(|| client).clone())
+}
+
 pub fn new_client() -> Result<Client> {
 	ClientBuilder::new()
 		.user_agent("curl/7.86.0")
 		.redirect(Policy::limited(5))
 		.build()
 		.map_err(reqwest_to_io)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+	use reqwest::header::{ETAG, HeaderMap, LAST_MODIFIED};
+
+	use crate::util::http::{FileHash, parse_checksum_file, validator};
+
+	#[test]
+	fn test_hash() {
+		let mut sha1 = FileHash::Sha1(String::new()).hasher();
+		sha1.update(b"abc");
+		assert_eq!(sha1.finalize(), "a9993e364706816aba3e25717850c26c9cd0d89d");
+		assert_eq!(parse_checksum_file("abcd  graalvm.tar.gz\n"), Some("abcd"));
+		assert_eq!(parse_checksum_file("abcd"), Some("abcd"));
+	}
+
+	#[test]
+	fn test_validator() {
+		let mut headers = HeaderMap::new();
+		assert_eq!(validator(&headers), None);
+		headers.insert(LAST_MODIFIED, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+		headers.insert(ETAG, "W/\"abc\"".parse().unwrap());
+		assert_eq!(validator(&headers), Some("Wed, 21 Oct 2015 07:28:00 GMT"));
+		headers.insert(ETAG, "\"abc\"".parse().unwrap());
+		assert_eq!(validator(&headers), Some("\"abc\""));
+	}
+}
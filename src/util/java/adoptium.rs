@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::util::errors::Result;
+use crate::util::java::{JavaChecksum, JavaPackage, JavaProvider};
+use crate::util::platform::JDK_ARCH;
+
+#[cfg(target_os = "linux")]
+static ADOPTIUM_OS: &str = "linux";
+#[cfg(target_os = "windows")]
+static ADOPTIUM_OS: &str = "windows";
+#[cfg(target_os = "macos")]
+static ADOPTIUM_OS: &str = "mac";
+
+/// Eclipse Temurin build from adoptium api (<https://api.adoptium.net/q/swagger-ui>)
+pub struct Adoptium {
+	api: String,
+}
+
+#[derive(Deserialize)]
+struct AdoptiumAsset {
+	binary: AdoptiumBinary,
+	release_name: String,
+}
+
+#[derive(Deserialize)]
+struct AdoptiumBinary {
+	package: AdoptiumPackage,
+}
+
+#[derive(Deserialize)]
+struct AdoptiumPackage {
+	name: String,
+	link: String,
+	checksum: Option<String>,
+}
+
+impl Adoptium {
+	pub fn new(api: impl Into<String>) -> Self {
+		Self { api: api.into() }
+	}
+}
+
+#[async_trait]
+impl JavaProvider for Adoptium {
+	fn prefix(&self) -> &'static str {
+		"temurin"
+	}
+
+	async fn find(&self, client: &Client, version: u8) -> Result<Option<JavaPackage>> {
+		// jre is smaller but not every version has it
+		for image in ["jre", "jdk"] {
+			let resp = client.get(format!("{}/v3/assets/latest/{version}/hotspot", self.api))
+				.query(&[("architecture", JDK_ARCH), ("image_type", image), ("os", ADOPTIUM_OS), ("vendor", "eclipse")])
+				.send()
+				.await?;
+			if !resp.status().is_success() {
+				debug!("adoptium respond {} for java {version} {image}", resp.status());
+				continue;
+			}
+			let assets: Vec<AdoptiumAsset> = resp.json().await?;
+			if let Some(asset) = assets.into_iter().next() {
+				debug!("found {} ({image})", asset.release_name);
+				let package = asset.binary.package;
+				return Ok(Some(JavaPackage {
+					file_name: package.name,
+					url: package.link,
+					checksum: match package.checksum {
+						Some(it) => { JavaChecksum::Sha256(it) }
+						None => { JavaChecksum::None }
+					},
+				}));
+			}
+		}
+		Ok(None)
+	}
+}
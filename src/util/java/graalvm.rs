@@ -0,0 +1,113 @@
+use std::mem;
+
+use async_trait::async_trait;
+use reqwest::Client;
+
+use crate::util::errors::{ErrorWrapper, Result};
+use crate::util::gh::{get_gh_latest_release, get_gh_release_from_tag, get_gh_releases, GhRelease, ReleaseAsset};
+use crate::util::java::{JavaChecksum, JavaPackage, JavaProvider};
+use crate::util::platform::{ARCH, JDK_ARCH, OS};
+
+static GRAALVM_REPO: &str = "graalvm/graalvm-ce-builds";
+
+/// fallback for java version that graalvm already dropped (java 8 and 11) in case it's out of release list
+static GRAALVM_LATEST_KNOWN_TAGS: &[(u8, &str)] = &[
+	(8, "vm-21.3.1"),
+	(11, "vm-22.3.0"),
+	(17, "vm-22.3.0"),
+];
+
+/// os name used by graalvm for jdk 17+
+#[cfg(target_os = "linux")]
+static COMMUNITY_OS: &str = "linux";
+#[cfg(target_os = "windows")]
+static COMMUNITY_OS: &str = "windows";
+#[cfg(target_os = "macos")]
+static COMMUNITY_OS: &str = "macos";
+
+pub struct GraalVm;
+
+#[async_trait]
+impl JavaProvider for GraalVm {
+	fn prefix(&self) -> &'static str {
+		"java"
+	}
+
+	async fn find(&self, _client: &Client, version: u8) -> Result<Option<JavaPackage>> {
+		let releases = get_gh_releases(GRAALVM_REPO).await?;
+		for release in releases.into_iter().filter(|it| !it.draft && !it.prerelease) {
+			if let Some(package) = GraalVmRelease::new(release).package(version) {
+				return Ok(Some(package));
+			}
+		}
+		let tag = GRAALVM_LATEST_KNOWN_TAGS.iter().find(|(ver, _)| *ver == version);
+		if let Some((_, tag)) = tag {
+			if let Some(release) = get_gh_release_from_tag(GRAALVM_REPO, tag).await? {
+				return Ok(GraalVmRelease::new(release).package(version));
+			}
+		}
+		Ok(None)
+	}
+}
+
+pub async fn get_graalvm_release() -> Result<GraalVmRelease> {
+	let release = get_gh_latest_release(GRAALVM_REPO).await?.ok_or(ErrorWrapper::NotFound)?;
+	Ok(GraalVmRelease::new(release))
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct GraalVmRelease {
+	inner: GhRelease,
+}
+
+impl GraalVmRelease {
+	pub fn new(mut release: GhRelease) -> Self {
+		let assets = mem::take(&mut release.assets);
+		release.assets = assets
+			.into_iter()
+			.filter(|it| it.name.ends_with(".tar.gz") || it.name.ends_with(".zip") || it.name.ends_with(".sha256"))
+			.collect();
+
+		Self {
+			inner: release
+		}
+	}
+
+	/// Find (archive, sha256 file) for java version
+	/// release name is `graalvm-ce-java17-linux-amd64-22.3.0.tar.gz` before jdk 17 and
+	/// `graalvm-community-jdk-21.0.2_linux-x64_bin.tar.gz` after
+	pub fn find_capable_release(&self, java_version: u8) -> Option<(&ReleaseAsset, Option<&ReleaseAsset>)> {
+		let legacy = format!("graalvm-ce-java{java_version}-{OS}-{ARCH}-");
+		let community = format!("graalvm-community-jdk-{java_version}");
+		let platform = format!("_{COMMUNITY_OS}-{JDK_ARCH}_bin");
+		let is_capable = |name: &str| {
+			if name.starts_with(&legacy) {
+				return true;
+			}
+			match name.strip_prefix(&community) {
+				// jdk-21.0.2 or jdk-21_ but not jdk-210
+				Some(rest) => { rest.starts_with(['.', '_']) && rest.contains(&platform) }
+				None => { false }
+			}
+		};
+		let archive = self.inner
+			.assets
+			.iter()
+			.find(|it| !it.name.ends_with(".sha256") && is_capable(&it.name))?;
+		let hash_name = format!("{}.sha256", archive.name);
+		let hash = self.inner.assets.iter().find(|it| it.name == hash_name);
+		Some((archive, hash))
+	}
+
+	pub fn package(&self, java_version: u8) -> Option<JavaPackage> {
+		let (archive, hash) = self.find_capable_release(java_version)?;
+		Some(JavaPackage {
+			file_name: archive.name.clone(),
+			url: archive.browser_download_url.clone(),
+			checksum: match hash {
+				Some(it) => { JavaChecksum::Url(it.browser_download_url.clone()) }
+				None => { JavaChecksum::None }
+			},
+		})
+	}
+}
@@ -0,0 +1,390 @@
+use std::io;
+use std::convert::AsRef;
+use std::ffi::OsString;
+use std::fs::Permissions;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use bstr::ByteSlice;
+use pedestal_rs::fs::path::relative_from;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tokio::fs::{create_dir_all, metadata, read_dir, remove_dir_all, remove_file, rename};
+use tokio::sync::RwLock;
+use tracing::{debug, error, warn};
+use tracing::log::info;
+
+use crate::util::artifact_cache;
+use crate::util::config::get_config;
+use crate::util::errors::Result;
+use crate::util::fs::extract_archive;
+use crate::util::http::{DownloadProgress, FileHash, new_client, parse_checksum_file};
+use crate::util::java::adoptium::Adoptium;
+use crate::util::java::graalvm::GraalVm;
+use crate::util::process::eval;
+
+pub mod adoptium;
+pub mod graalvm;
+
+pub struct Java {
+	java_path: String,
+}
+
+/// Source of java runtime that can be downloaded
+#[async_trait]
+pub trait JavaProvider: Send + Sync {
+	/// Prefix of folder inside `java_runtime` eg. `java17` for graalvm
+	fn prefix(&self) -> &'static str;
+
+	/// Find archive of java major version for current platform; None if provider doesn't have it
+	async fn find(&self, client: &Client, version: u8) -> Result<Option<JavaPackage>>;
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JavaProviderKind {
+	/// GraalVM CE from github releases
+	#[default]
+	GraalVm,
+	/// Eclipse Temurin from adoptium api
+	Adoptium,
+}
+
+impl JavaProviderKind {
+	pub async fn provider(self) -> Box<dyn JavaProvider> {
+		let config = get_config().await;
+		match self {
+			JavaProviderKind::GraalVm => { Box::new(GraalVm) }
+			JavaProviderKind::Adoptium => { Box::new(Adoptium::new(config.java.adoptium_api.trim_end_matches('/'))) }
+		}
+	}
+}
+
+/// Downloadable java archive
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct JavaPackage {
+	/// Archive name (`.tar.gz` or `.zip`)
+	pub file_name: String,
+	pub url: String,
+	pub checksum: JavaChecksum,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum JavaChecksum {
+	/// sha256 published by provider
+	Sha256(String),
+	/// Url to sha256 file
+	Url(String),
+	None,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Serialize, Clone)]
+pub struct JavaInfo {
+	/// `java-<major>[-graalvm[-ee]]-<source>`, source is folder in `java_runtime`, `path` for java in $PATH or hash of its path
+	id: String,
+	path: String,
+	version: u8,
+	/// None if it's not graalvm; true for enterprise edition
+	is_graalvm: Option<bool>,
+}
+
+impl Java {
+	pub fn new(path: impl Into<String>) -> Java {
+		Self {
+			java_path: path.into()
+		}
+	}
+
+
+	pub async fn get_info(&self) -> io::Result<JavaInfo> {
+		let out = eval([&self.java_path, "-version"]).await?;
+		let version = (|| {
+			let mut lines = out.split(|b| b == &b'\n');
+			let info = lines.next()?;
+			let mut tokens = info.split(|b| b == &b'"');
+			tokens.next()?;// java version "
+			let ver = tokens.next()?; // x.y.z
+			let mut vers = ver.split(|it| it == &b'.');
+			let first = vers.next()?;
+			if first != b"1" {
+				String::from_utf8_lossy(first).parse::<u8>().ok()
+			} else {
+				// java version < 9 use 1. prefix eg. 1.8 for java 8
+				String::from_utf8_lossy(vers.next()?).parse::<u8>().ok()
+			}
+		})();
+		let is_graalvm = if out.contains_str("GraalVM") {
+			Some(out.contains_str("EE"))
+		} else {
+			None
+		};
+		let version = version.ok_or_else(|| io::Error::from(ErrorKind::InvalidFilename))?;
+		let mut id = String::new();
+		id.push_str("java-");
+		id.push_str(&version.to_string());
+		if let Some(is_graalvm) = is_graalvm {
+			id.push_str("-graalvm");
+			if is_graalvm {
+				id.push_str("-ee");
+			}
+		}
+		let mut info = JavaInfo {
+			id,
+			version,
+			path: self.java_path.to_string(),
+			is_graalvm,
+		};
+		// the same version can be installed by several providers and found in $PATH too
+		let source = match info.runtime_dir() {
+			Some(dir) => { dir.file_name().unwrap_or_default().to_string_lossy().to_string() }
+			None if !info.path.contains(['/', '\\']) => { "path".to_string() }
+			None => { Sha256::digest(info.path.as_bytes())[..4].iter().map(|it| format!("{:02x}", it)).collect() }
+		};
+		info.id.push('-');
+		info.id.push_str(&source);
+		Ok(info)
+	}
+}
+
+impl JavaInfo {
+	pub fn performance_args(&self) -> &'static [&'static str] {
+		// graalvm provide faster cold startup time than other java provider
+		match self.is_graalvm {
+			None => {
+				&[]
+			}
+			Some(false) => {
+				&GRAALVM_EE_ARGS[..2]
+			}
+			Some(true) => {
+				GRAALVM_EE_ARGS
+			}
+		}
+	}
+
+	/// Check if jvm argument is one of [Self::performance_args] from any runtime
+	pub fn is_performance_arg(arg: &str) -> bool {
+		GRAALVM_EE_ARGS.contains(&arg)
+	}
+
+	pub fn id(&self) -> &str {
+		&self.id
+	}
+
+	pub fn path(&self) -> &str {
+		&self.path
+	}
+
+	pub fn version(&self) -> u8 {
+		self.version
+	}
+
+	/// Folder of runtime that downloaded into `java_runtime`; None for java from $PATH
+	pub fn runtime_dir(&self) -> Option<&Path> {
+		let path = Path::new(&self.path);
+		// <java_runtime>/<runtime>/bin/java
+		let dir = path.parent()?.parent()?;
+		if dir.parent()? == Path::new(JAVA_RUNTIME_DIR) {
+			Some(dir)
+		} else {
+			None
+		}
+	}
+
+	pub fn path_for(&self, dir: impl AsRef<Path>) -> OsString {
+		if self.path.contains('/') {
+			relative_from(&self.path, dir).into_os_string()
+		} else {
+			// maybe it's executable that need to look up from $PATH just return it directly
+			OsString::from(&self.path)
+		}
+	}
+}
+
+/// first 2 arguments are also used by community edition
+static GRAALVM_EE_ARGS: &[&str] = &[
+	"-XX:+UseJVMCICompiler",
+	"-XX:+UseJVMCINativeLibrary",
+	"-Dgraal.UsePriorityInlining=true",
+	"-Dgraal.TuneInlinerExploration=1",
+	"-Dgraal.CompilerConfiguration=enterprise",
+	"-Dgraal.MitigateSpeculativeExecutionAttacks=none"
+];
+
+static JAVA_RUNTIME_DIR: &str = "java_runtime";
+
+pub static JAVA_RUNTIMES: RwLock<Vec<JavaInfo>> = RwLock::const_new(Vec::new());
+
+pub struct JavaManager;
+
+impl JavaManager {
+	pub async fn versions() -> Vec<JavaInfo> {
+		let runtimes = JAVA_RUNTIMES.read().await;
+		runtimes.clone()
+	}
+
+	pub async fn get_by_id(id: &str) -> Option<JavaInfo> {
+		let runtimes = JAVA_RUNTIMES.read().await;
+		runtimes.iter().find(|it| it.id == id).cloned()
+	}
+
+	/// Find installed runtime of java version, download it if there's none
+	pub async fn get_version(version: u8, progress: &dyn DownloadProgress) -> Option<JavaInfo> {
+		let existing = JAVA_RUNTIMES.read().await.iter().find(|it| it.version == version).cloned();
+		if existing.is_none() {
+			info!("java version {version} was not found");
+			info!("downloading java version {version}..");
+			return match Self::download_version_with(version, progress).await {
+				Ok(it) => { Some(it) }
+				Err(err) => {
+					error!("Failed to download java {version}: {err:?}");
+					None
+				}
+			};
+		}
+		existing
+	}
+
+	pub async fn scan() -> io::Result<()> {
+		let mut javas = vec![];
+		info!("scanning for java in {JAVA_RUNTIME_DIR:?}");
+		if metadata(JAVA_RUNTIME_DIR).await.is_ok() {
+			let mut folders = read_dir(JAVA_RUNTIME_DIR).await?;
+			while let Some(folder) = folders.next_entry().await? {
+				if folder.file_type().await?.is_dir() {
+					let mut path = folder.path();
+					path.push("bin/java");
+					let java = Java::new(path.to_string_lossy());
+					match java.get_info().await {
+						Ok(info) => {
+							debug!("found {path:?} version {}", info.version);
+							javas.push(info);
+						}
+						Err(err) => {
+							warn!("Skipped {:?}: {err}", folder.path());
+						}
+					}
+				}
+			}
+		}
+		// scan regular java in $PATH
+		if let Ok(info) = Java::new("java").get_info().await {
+			info!("scanning for java in $PATH");
+			debug!("found \"{}\" version {}", info.path, info.version);
+			javas.push(info);
+		}
+		*JAVA_RUNTIMES.write().await = javas;
+		Ok(())
+	}
+
+	/// Download java runtime using provider from config
+	pub async fn download_version(ver: u8) -> Result<JavaInfo> {
+		Self::download_version_with(ver, &()).await
+	}
+
+	/// Delete runtime from `java_runtime`, java in $PATH can't be removed
+	pub async fn remove(id: &str) -> Result<Option<JavaInfo>> {
+		let Some(java) = Self::get_by_id(id).await else {
+			return Ok(None);
+		};
+		let dir = java.runtime_dir()
+			.ok_or_else(|| io::Error::new(ErrorKind::PermissionDenied, format!("{id} is not managed by crate")))?;
+		info!("removing java runtime at {dir:?}");
+		remove_dir_all(dir).await?;
+		JAVA_RUNTIMES.write().await.retain(|it| it.path != java.path);
+		Ok(Some(java))
+	}
+
+	pub async fn download_version_with(ver: u8, progress: &dyn DownloadProgress) -> Result<JavaInfo> {
+		if metadata(JAVA_RUNTIME_DIR).await.is_err() {
+			create_dir_all(JAVA_RUNTIME_DIR).await?;
+		}
+		let kind = get_config().await.java.provider;
+		let provider = kind.provider().await;
+		let client = new_client()?;
+		debug!("preparing to download java {ver} from {kind:?}");
+		progress.set_phase(&format!("downloading java {ver}"));
+		let package = provider.find(&client, ver).await?
+			.ok_or_else(|| io::Error::new(ErrorKind::Unsupported, format!("java version {ver} is not available from {kind:?}")))?;
+		Self::download_package(&client, provider.prefix(), ver, &package, progress).await
+	}
+
+	async fn download_package(client: &Client, prefix: &str, version: u8, package: &JavaPackage, progress: &dyn DownloadProgress) -> Result<JavaInfo> {
+		let archive = Self::download_file(client, package, progress).await?;
+		let outdir = AsRef::<Path>::as_ref(JAVA_RUNTIME_DIR).join(format!("{prefix}{version}"));
+		if metadata(&outdir).await.is_ok() {
+			remove_dir_all(&outdir).await?;
+		}
+		info!("extracting file {archive:?} to {outdir:?}");
+		progress.set_phase(&format!("extracting java {version}"));
+		extract_archive(&archive, &outdir).await?;
+		remove_file(&archive).await.ok();
+		let java = Self::try_purge_jdk(&outdir).await?;
+
+		let mut runtimes = JAVA_RUNTIMES.write().await;
+		runtimes.retain(|it| it.path != java.path);
+		runtimes.push(java.clone());
+		info!("java {version} has been downloaded");
+		Ok(java)
+	}
+
+	async fn download_file(client: &Client, package: &JavaPackage, progress: &dyn DownloadProgress) -> Result<PathBuf> {
+		debug!("Downloading java archive from {}", package.url);
+		let target = AsRef::<Path>::as_ref(JAVA_RUNTIME_DIR).join(&package.file_name);
+		let expected = match &package.checksum {
+			JavaChecksum::Sha256(hash) => { Some(FileHash::Sha256(hash.clone())) }
+			JavaChecksum::Url(url) => {
+				debug!("Getting hash from {url}");
+				let content = client.get(artifact_cache::rewrite(url).await).send().await?.error_for_status()?.text().await?;
+				let hash = parse_checksum_file(&content)
+					.ok_or_else(|| io::Error::new(ErrorKind::InvalidData, format!("Invalid checksum file {url}")))?;
+				Some(FileHash::Sha256(hash.to_string()))
+			}
+			JavaChecksum::None => { None }
+		};
+		let out_file = artifact_cache::fetch(client, &package.url, target, expected.as_ref(), progress).await?;
+		if expected.is_none() {
+			warn!("Downloaded file at '{out_file:?}' but can't verify its hash");
+		}
+		Ok(out_file)
+	}
+
+	pub async fn try_purge_jdk(folder: impl AsRef<Path>) -> io::Result<JavaInfo> {
+		let folder = folder.as_ref();
+		// macos archive put runtime inside bundle
+		let bundle = folder.join("Contents/Home");
+		// have bundled jre
+		let jre = folder.join("jre");
+		if metadata(&bundle).await.is_ok() {
+			let parent = folder.parent().unwrap();
+			let temp = parent.join("tmp");
+			rename(bundle, &temp).await?;
+			remove_dir_all(folder).await?;
+			rename(temp, folder).await?;
+		} else if metadata(&jre).await.is_ok() {
+			let parent = folder.parent().unwrap();
+			// may cause data race
+			let temp = parent.join("tmp");
+			rename(jre, &temp).await?;
+			remove_dir_all(folder).await?;
+			rename(temp, folder).await?;
+		} else {
+			remove_file(folder.join("src.zip")).await.ok();
+		}
+
+		let java_path = folder.join("bin/java");
+		#[cfg(target_os = "linux")]
+		{
+			use std::os::unix::prelude::PermissionsExt;
+			let mut perm = metadata(&java_path).await?.permissions();
+			Permissions::set_mode(&mut perm, 0o755);
+			fs::set_permissions(&java_path, perm).await?;
+		}
+		Java::new(java_path.to_string_lossy()).get_info().await
+	}
+}
+
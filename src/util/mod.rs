@@ -11,6 +11,7 @@ use zip::write::FileOptions;
 
 use crate::file_scanner::scan_recursive;
 
+pub mod artifact_cache;
 pub mod http;
 pub mod errors;
 pub mod fs;
@@ -25,6 +26,7 @@ pub mod time;
 pub mod modification;
 pub mod serde;
 pub mod string;
+pub mod glob;
 
 pub async fn get_zip_file(path: PathBuf) -> Result<PathBuf> {
 	if path.is_file() {
@@ -3,6 +3,12 @@ pub static ARCH: &str = "amd64";
 #[cfg(target_arch = "aarch64")]
 pub static ARCH: &str = "aarch64";
 
+/// Arch name used by adoptium and graalvm for jdk 17+
+#[cfg(target_arch = "x86_64")]
+pub static JDK_ARCH: &str = "x64";
+#[cfg(target_arch = "aarch64")]
+pub static JDK_ARCH: &str = "aarch64";
+
 
 #[cfg(target_os = "linux")]
 pub static OS: &str = "linux";
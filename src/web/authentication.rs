@@ -10,6 +10,7 @@ use tracing::{debug, info};
 
 use jwt::{DECODE_KEY, DEFAULT_PRI, DEFAULT_PUB, ENCODE_KEY};
 pub use jwt::{Authorization, sign_jwt};
+pub use manifest_key::{init_manifest_key, ManifestKey, sign_manifest};
 
 use crate::util::config::get_config;
 
@@ -93,7 +94,7 @@ pub async fn init() -> std::io::Result<()> {
 			}
 		}
 	}
-	Ok(())
+	init_manifest_key().await
 }
 
 mod jwt {
@@ -193,4 +194,92 @@ mod jwt {
 			Err(ErrorWrapper::custom(StatusCode::UNAUTHORIZED, "Unauthorized"))
 		}
 	}
+}
+
+mod manifest_key {
+	use std::io;
+	use std::io::ErrorKind;
+
+	use jsonwebtoken::{Algorithm, encode, EncodingKey, Header};
+	use openssl::pkey::PKey;
+	use serde::Serialize;
+	use sha2::{Digest, Sha256};
+	use tokio::fs::{metadata, OpenOptions};
+	use tokio::io::AsyncWriteExt;
+	use tokio::sync::OnceCell;
+	use tracing::{debug, info};
+
+	use crate::util::config::get_config;
+
+	static MANIFEST_KEY: OnceCell<(EncodingKey, ManifestKey)> = OnceCell::const_new();
+
+	static DEFAULT_PRI: &str = "manifest.key";
+	static DEFAULT_PUB: &str = "manifest.pub";
+
+	/// Public part of manifest signing key
+	#[derive(Serialize, Debug, Clone)]
+	pub struct ManifestKey {
+		pub algorithm: &'static str,
+		/// Pem encoded public key
+		pub key: String,
+		/// Sha256 of public key (der), client should pin this
+		pub fingerprint: String,
+	}
+
+	/// Generate key pair if missing and load it
+	pub async fn init_manifest_key() -> io::Result<()> {
+		let (pri_path, pub_path) = {
+			let cfg = &get_config().await.manifest_signing;
+			(
+				Some(cfg.private_key.as_str()).filter(|it| !it.is_empty()).unwrap_or(DEFAULT_PRI).to_string(),
+				Some(cfg.public_key.as_str()).filter(|it| !it.is_empty()).unwrap_or(DEFAULT_PUB).to_string(),
+			)
+		};
+		if metadata(&pri_path).await.is_err() && metadata(&pub_path).await.is_err() {
+			info!("generating new manifest signing key");
+			let key = PKey::generate_ed25519()?;
+			let mut options = OpenOptions::new();
+			options.write(true).create_new(true);
+			// only owner can read private key
+			#[cfg(unix)]
+			options.mode(0o600);
+			options.open(&pri_path).await?.write_all(&key.private_key_to_pem_pkcs8()?).await?;
+			tokio::fs::write(&pub_path, key.public_key_to_pem()?).await?;
+		}
+		debug!("loading manifest signing key");
+		let enc_key = tokio::fs::read(&pri_path).await?;
+		let public = PKey::public_key_from_pem(&tokio::fs::read(&pub_path).await?)?;
+		// client would pin fingerprint of key that can't verify signature
+		if PKey::private_key_from_pem(&enc_key)?.public_key_to_der()? != public.public_key_to_der()? {
+			return Err(io::Error::new(ErrorKind::InvalidData, format!("{pub_path} is not public key of {pri_path}")));
+		}
+		let encoding_key = EncodingKey::from_ed_pem(&enc_key)
+			.map_err(|err| io::Error::new(ErrorKind::InvalidData, format!("Invalid manifest signing key {pri_path}: {err}")))?;
+		let fingerprint = Sha256::digest(public.public_key_to_der()?)
+			.iter()
+			.map(|it| format!("{:02x}", it))
+			.collect::<Vec<_>>()
+			.join(":");
+		let key = ManifestKey {
+			algorithm: "EdDSA",
+			key: String::from_utf8_lossy(&public.public_key_to_pem()?).to_string(),
+			fingerprint,
+		};
+		MANIFEST_KEY.get_or_init(|| async { (encoding_key, key) }).await;
+		Ok(())
+	}
+
+	impl ManifestKey {
+		pub fn get() -> &'static ManifestKey {
+			&MANIFEST_KEY.get().expect("Manifest signing key").1
+		}
+	}
+
+	/// Compact jws with payload as its claims, `kid` is key fingerprint
+	pub async fn sign_manifest<T: Serialize + Send + 'static>(payload: T) -> anyhow::Result<String> {
+		let (key, public) = MANIFEST_KEY.get().expect("Manifest signing key");
+		let mut header = Header::new(Algorithm::EdDSA);
+		header.kid = Some(public.fingerprint.clone());
+		Ok(tokio_rayon::spawn(move || encode(&header, &payload, key)).await?)
+	}
 }
\ No newline at end of file
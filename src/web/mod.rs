@@ -1,4 +1,4 @@
-pub(crate) use authentication::{sign_jwt};
+pub(crate) use authentication::{init_manifest_key, ManifestKey, sign_jwt};
 
 pub mod http;
 
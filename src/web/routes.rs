@@ -19,6 +19,7 @@ use tracing::trace;
 use crate::info::GlobalInfo;
 use crate::util::config::get_config;
 use crate::util::string::EmptyExt;
+use crate::web::authentication::ManifestKey;
 use crate::web::v1::get_v1;
 
 pub fn build_route(mut route: Router) -> Router {
@@ -32,6 +33,7 @@ pub fn build_route(mut route: Router) -> Router {
 	}
 	route
 		.route("/api", get(api_info))
+		.route("/.well-known/manifest-key", get(manifest_key))
 		.nest("/api/v1", get_v1())
 		.fallback(not_found)
 }
@@ -40,6 +42,11 @@ async fn api_info() -> impl IntoResponse {
 	Json(GlobalInfo::default())
 }
 
+/// Public key to verify signed manifest
+async fn manifest_key() -> impl IntoResponse {
+	Json(ManifestKey::get())
+}
+
 async fn handler(ws: Option<WebSocketUpgrade>, State(client): State<Client<HttpConnector>>, mut req: Request<Body>) -> Response {
 	if let Some(ws) = ws {
 		let mut uri = {
@@ -8,15 +8,23 @@ use crate::info::DetailedInfo;
 use crate::util::errors::{ResultBase, ResponseResult};
 
 mod auth;
+mod curseforge;
 mod instance;
+mod java;
+mod jobs;
+mod modrinth;
 mod user;
+mod versions;
 
 pub fn get_v1() -> Router {
 	debug!("Configuring v1 routes");
 	Router::new()
 		.nest("/auth", auth::build())
 		.nest("/instance", instance::build())
+		.nest("/java", java::build())
+		.nest("/jobs", jobs::build())
 		.nest("/user", user::build())
+		.nest("/versions", versions::build())
 		.route("/err", get(err))
 		.route("/success", get(success))
 		.route("/info", get(info))
@@ -0,0 +1,137 @@
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::{Json, Router};
+use axum::routing::{get, post};
+use hashbrown::HashSet;
+use serde::{Deserialize, Serialize};
+use tracing::log::debug;
+
+use crate::instance::mc_mod::ModSource;
+use crate::manager::instance_manager::InstanceManagerExt;
+use crate::manager::job_manager::JobManager;
+use crate::mc::curseforge;
+use crate::mc::curseforge::{CurseForgeTarget, ManualDownload};
+use crate::util::errors::ErrorWrapper;
+use crate::util::errors::rest::{accepted, conflict, got, not_found, Resp};
+use crate::util::http::{DownloadProgress, new_client};
+use crate::web::authentication::Authorization;
+
+/// Nested in `/instance/:name/curseforge`, every request is filtered by loader and version of the instance
+pub fn build() -> Router {
+	debug!("Configuring curseforge routes");
+	Router::new()
+		.route("/search", get(search))
+		.route("/project/:project", get(files))
+		.route("/install", post(install))
+}
+
+#[derive(Deserialize)]
+struct InstancePath {
+	name: String,
+}
+
+#[derive(Deserialize)]
+struct ProjectPath {
+	name: String,
+	project: u64,
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+	#[serde(default)]
+	query: String,
+	#[serde(default)]
+	index: u32,
+	#[serde(default = "default_page_size")]
+	page_size: u32,
+}
+
+const fn default_page_size() -> u32 { 20 }
+
+fn not_configured() -> Resp {
+	Err(ErrorWrapper::custom(StatusCode::SERVICE_UNAVAILABLE, "CurseForge api key is not configured"))
+}
+
+async fn target(m: &InstanceManagerExt, name: &str) -> Option<CurseForgeTarget> {
+	let instance = m.read().await.find(name)?;
+	let instance = instance.read().await;
+	Some(CurseForgeTarget::new(&instance.mod_type, instance.version()))
+}
+
+async fn search(Path(InstancePath { name }): Path<InstancePath>,
+                Query(SearchQuery { query, index, page_size }): Query<SearchQuery>,
+                m: InstanceManagerExt,
+                _: Authorization,
+) -> Resp {
+	if !curseforge::configured().await {
+		return not_configured();
+	}
+	let Some(target) = target(&m, &name).await else {
+		return not_found();
+	};
+	got(curseforge::search(&new_client()?, &query, &target, index, page_size.min(50)).await?)
+}
+
+async fn files(Path(ProjectPath { name, project }): Path<ProjectPath>, m: InstanceManagerExt, _: Authorization) -> Resp {
+	if !curseforge::configured().await {
+		return not_configured();
+	}
+	let Some(target) = target(&m, &name).await else {
+		return not_found();
+	};
+	got(curseforge::files(&new_client()?, project, &target).await?)
+}
+
+#[derive(Deserialize)]
+struct InstallRequest {
+	project: u64,
+	/// Latest compatible file if it's not specified
+	#[serde(default)]
+	file: Option<u64>,
+}
+
+/// Result of install job
+#[derive(Serialize)]
+struct InstallResult {
+	/// Files that has to be downloaded from website and uploaded to `/instance/:name/mods`
+	manual: Vec<ManualDownload>,
+}
+
+/// Resolve project with its required dependencies then download them in background,
+/// files that can't be downloaded are in job result
+async fn install(Path(InstancePath { name }): Path<InstancePath>,
+                 m: InstanceManagerExt,
+                 _: Authorization,
+                 Json(InstallRequest { project, file }): Json<InstallRequest>,
+) -> Resp {
+	if !curseforge::configured().await {
+		return not_configured();
+	}
+	let Some(instance) = m.read().await.find(&name) else {
+		return not_found();
+	};
+	let job_name = format!("install curseforge {project} to {name}");
+	let (target, folder, installed) = {
+		let instance = instance.read().await;
+		let installed: HashSet<u64> = instance.mods.iter()
+			.filter_map(|it| match &it.source {
+				Some(ModSource::CurseForge { project_id, .. }) => { Some(*project_id) }
+				_ => { None }
+			})
+			.collect();
+		(CurseForgeTarget::new(&instance.mod_type, instance.version()), instance.mod_dir()?, installed)
+	};
+	let job = JobManager::spawn(job_name, |job| async move {
+		let client = new_client()?;
+		job.set_phase("resolving dependencies");
+		let resolved = curseforge::resolve(&client, project, file, &target, &installed).await?;
+		let mods = curseforge::install(&client, &resolved.files, &folder, &*job).await?;
+		instance.write().await.record_mods(mods).await?;
+		job.set_result(InstallResult { manual: resolved.manual });
+		Ok(())
+	}).await;
+	let Some(job) = job else {
+		return conflict();
+	};
+	accepted(job.info())
+}
@@ -1,27 +1,98 @@
+use std::path::PathBuf;
+
 use axum::{Json, Router};
-use axum::extract::Path;
-use axum::routing::{get, post};
-use serde::Deserialize;
-use tracing::log::debug;
+use axum::extract::{DefaultBodyLimit, Multipart, Path, Query};
+use axum::body::{Body, BoxBody, boxed};
+use axum::http::{HeaderMap, HeaderValue, Request, Response, StatusCode};
+use axum::http::header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE, ETAG, HOST, IF_NONE_MATCH, IF_RANGE, RANGE};
+use axum::response::IntoResponse;
+use axum::routing::{get, patch, post};
+use serde::{Deserialize, Serialize};
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+use tokio::fs::{create_dir_all, File, remove_file};
+use tokio::io::AsyncWriteExt;
+use tracing::log::{debug, warn};
 
-use crate::instance::mc_instance::ModType;
-use crate::manager::instance_manager::InstanceManagerExt;
-use crate::util::errors::{ResponseResult, ResultBase};
-use crate::util::errors::rest::{conflict, created, got, no_content, not_found, Resp};
-use crate::web::authentication::Authorization;
+use crate::instance::manifest::{Manifest, ManifestHistory};
+use crate::instance::mc_instance::{McInstance, ModType};
+use crate::instance::mc_mod::InstalledMod;
+use crate::instance::server_import;
+use crate::instance::server_import::ImportMode;
+use crate::instance::zip_cache;
+use crate::instance::zip_cache::ZipKind;
+use crate::manager::instance_manager::{InstanceManager, InstanceManagerExt};
+use crate::manager::job_manager::{JobInfo, JobManager};
+use crate::manager::update_manager::UpdateManager;
+use crate::instance::mc_instance::bad_upload;
+use crate::mc::curseforge::ManualDownload;
+use crate::mc::modpack::Modpack;
+use crate::mc::multimc;
+use crate::mc::multimc::UpdaterClient;
+use crate::util::config::get_config;
+use crate::util::http::new_client;
+use crate::util::java::JavaManager;
+use crate::util::errors::{ErrorWrapper, ResponseResult, ResultBase};
+use crate::util::errors::rest::{accepted, conflict, got, no_content, not_found, Resp};
+use crate::web::authentication::{Authorization, sign_manifest};
+use crate::web::v1::{curseforge, modrinth};
 
 pub fn build() -> Router {
 	debug!("Configuring instance routes");
 	Router::new()
 		.route("/", get(all))
 		.route("/:name", get(info).delete(delete).post(create))
+		.route("/:name/java", post(java))
+		.route("/:name/import", post(import).layer(DefaultBodyLimit::max(MAX_MODPACK)))
+		.route("/:name/import/server", post(import_server))
+		// read by mmcupdater client which has no user token, manifest is signed instead
+		.route("/:name/manifest", get(manifest))
+		.route("/:name/manifest/diff", get(manifest_diff))
+		.route("/:name/dist.zip", get(dist_zip))
+		.route("/:name/config.zip", get(config_zip))
+		.route("/:name/dist/*path", get(dist_file))
+		.route("/:name/export/multimc", get(export_multimc))
+		.route("/:name/mods", get(mods).post(upload_mods).layer(DefaultBodyLimit::max(MAX_UPLOAD)))
+		.route("/:name/mods/validate", get(validate_mods))
+		.route("/:name/mods/updates", get(mod_updates).post(apply_updates))
+		.route("/:name/mods/history", get(mod_history))
+		.route("/:name/mods/history/:id/rollback", post(rollback_mod))
+		.route("/:name/mods/:file", patch(toggle_mod).delete(remove_mod))
+		.nest("/:name/curseforge", curseforge::build())
+		.nest("/:name/modrinth", modrinth::build())
 }
 
+/// Mod jar can be quite large
+const MAX_UPLOAD: usize = 256 * 1024 * 1024;
+/// Modpack with overrides can be larger
+const MAX_MODPACK: usize = 1024 * 1024 * 1024;
+
 #[derive(Deserialize)]
 struct InstancePath {
 	name: String,
 }
 
+#[derive(Deserialize)]
+struct DistPath {
+	name: String,
+	/// Relative to instance folder, eg. `mods/jei.jar`
+	path: String,
+}
+
+#[derive(Deserialize)]
+struct ModPath {
+	name: String,
+	/// File name of enabled jar, even if it's disabled
+	file: String,
+}
+
+#[derive(Deserialize)]
+struct RestartQuery {
+	/// Restart server after change if it's running
+	#[serde(default)]
+	restart: bool,
+}
+
 async fn all(m: InstanceManagerExt, _: Authorization) -> ResponseResult<Vec<String>> {
 	let manager = m.read().await;
 	ResultBase::success(manager.names())
@@ -68,15 +139,526 @@ async fn create(Path(InstancePath { name }): Path<InstancePath>,
                 _: Authorization,
                 Json(InstanceCreate { version, typ }): Json<InstanceCreate>,
 ) -> Resp {
+	let job_name = format!("create instance {name}");
+	if m.read().await.find(&name).is_some() {
+		return conflict();
+	}
+	let manager = m.0.clone();
+	let job = JobManager::spawn(job_name, |job| async move {
+		InstanceManager::new_instance(&manager, &name, &version, typ, &*job).await?;
+		Ok(())
+	}).await;
+	let Some(job) = job else {
+		return conflict();
+	};
+	accepted(job.info())
+}
+
+#[derive(Serialize)]
+struct ImportResponse {
+	job: JobInfo,
+	/// Name of modpack
+	modpack: String,
+}
+
+/// Create instance from `.mrpack` or curseforge modpack zip (multipart field `file`).
+/// Curseforge files that have to be uploaded after import are listed in `result.manual` of the job
+async fn import(Path(InstancePath { name }): Path<InstancePath>,
+                m: InstanceManagerExt,
+                _: Authorization,
+                mut multipart: Multipart,
+) -> Resp {
+	let job_name = format!("create instance {name}");
+	// don't receive the whole upload just to reject it
+	if m.read().await.find(&name).is_some() || JobManager::find_running(&job_name).await.is_some() {
+		return conflict();
+	}
+	let dl_folder = PathBuf::from("download");
+	create_dir_all(&dl_folder).await?;
+	// concurrent upload of the same name must not share the file
+	let archive = dl_folder.join(format!("{}-{:016x}.modpack.zip", name.replace(['/', '\\'], "_"), rand::random::<u64>()));
+	let mut received = false;
+	while let Some(mut field) = multipart.next_field().await.map_err(bad_upload)? {
+		if field.name() != Some("file") {
+			continue;
+		}
+		let mut file = File::create(&archive).await?;
+		while let Some(data) = field.chunk().await.map_err(bad_upload)? {
+			file.write_all(&data).await?;
+		}
+		file.flush().await?;
+		received = true;
+		break;
+	}
+	if !received {
+		return Err(ErrorWrapper::custom(StatusCode::BAD_REQUEST, "Modpack file is missing"));
+	}
+	let mut pack = match Modpack::read(&archive).await {
+		Ok(it) => { it }
+		Err(err) => {
+			warn!("Failed to read modpack: {err}");
+			remove_file(&archive).await.ok();
+			return Err(ErrorWrapper::custom(StatusCode::BAD_REQUEST, "Invalid or unsupported modpack"));
+		}
+	};
+	if pack.needs_curseforge() && !crate::mc::curseforge::configured().await {
+		remove_file(&archive).await.ok();
+		return Err(ErrorWrapper::custom(StatusCode::SERVICE_UNAVAILABLE, "CurseForge api key is not configured"));
+	}
+	let modpack = pack.name.clone();
+	let manager = m.0.clone();
+	let upload = archive.clone();
+	let job = JobManager::spawn(job_name, |job| async move {
+		let result = async {
+			let manual = pack.resolve(&new_client()?, &*job).await?;
+			InstanceManager::import_modpack(&manager, &name, &pack, &archive, &*job).await?;
+			job.set_result(ImportResult { manual });
+			Ok(())
+		}.await;
+		remove_file(&archive).await.ok();
+		result
+	}).await;
+	let Some(job) = job else {
+		remove_file(&upload).await.ok();
+		return conflict();
+	};
+	accepted(ImportResponse { job: job.info(), modpack })
+}
+
+/// Result of import job
+#[derive(Serialize)]
+struct ImportResult {
+	/// Curseforge files that have to be uploaded after import
+	manual: Vec<ManualDownload>,
+}
+
+#[derive(Deserialize)]
+struct ServerImport {
+	/// Existing server folder on this host
+	path: PathBuf,
+	#[serde(default)]
+	mode: ImportMode,
+}
+
+#[derive(Serialize)]
+struct ServerImportResponse {
+	job: JobInfo,
+	/// Detected loader, version and launch arguments
+	instance: McInstance,
+	mods: Vec<InstalledMod>,
+}
+
+/// Take over existing server folder inside `import.roots`, it's symlinked (default) or moved into instance folder if `import.allow_move`
+async fn import_server(Path(InstancePath { name }): Path<InstancePath>,
+                       m: InstanceManagerExt,
+                       _: Authorization,
+                       Json(ServerImport { path, mode }): Json<ServerImport>,
+) -> Resp {
+	let job_name = format!("create instance {name}");
+	if m.read().await.find(&name).is_some() {
+		return conflict();
+	}
+	if !path.is_dir() {
+		return Err(ErrorWrapper::custom(StatusCode::BAD_REQUEST, "Server folder is not found"));
+	}
+	let path = {
+		let config = get_config().await;
+		if mode == ImportMode::Move && !config.import.allow_move {
+			return Err(ErrorWrapper::custom(StatusCode::FORBIDDEN, "Moving server folder is disabled, use symlink"));
+		}
+		match server_import::inside_roots(&path, &config.import.roots) {
+			Some(it) => { it }
+			None => {
+				return Err(ErrorWrapper::custom(StatusCode::FORBIDDEN, "Folder is outside of import roots"));
+			}
+		}
+	};
+	let instance = match McInstance::detect(&path).await {
+		Ok(it) => { it }
+		Err(err) => {
+			warn!("Failed to detect server in {path:?}: {err}");
+			return Err(ErrorWrapper::custom(StatusCode::BAD_REQUEST, "Can't detect server in folder"));
+		}
+	};
+	let mods = instance.scan_mods(false).await?;
+	let detected = instance.clone();
+	let manager = m.0.clone();
+	let job = JobManager::spawn(job_name, |job| async move {
+		InstanceManager::import_server(&manager, &name, instance, &path, mode, &*job).await?;
+		Ok(())
+	}).await;
+	let Some(job) = job else {
+		return conflict();
+	};
+	accepted(ServerImportResponse { job: job.info(), instance: detected, mods })
+}
+
+/// True if `If-None-Match` contains etag
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+	headers.get_all(IF_NONE_MATCH).iter()
+		.filter_map(|it| it.to_str().ok())
+		.flat_map(|it| it.split(','))
+		.map(|it| it.trim().trim_start_matches("W/"))
+		.any(|it| it == etag || it == "*")
+}
+
+#[derive(Deserialize)]
+struct SignedQuery {
+	/// Respond with compact jws signed by key from `/.well-known/manifest-key`
+	#[serde(default)]
+	signed: bool,
+}
+
+/// Json or jws of data
+async fn signed<T: Serialize + Send + 'static>(data: T, signed: bool) -> Resp {
+	if !signed {
+		return got(data);
+	}
+	let mut res = sign_manifest(data).await?.into_response();
+	res.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/jose"));
+	Ok(res)
+}
+
+/// Files client has to download, revalidate it with `If-None-Match`
+async fn manifest(Path(InstancePath { name }): Path<InstancePath>,
+                  Query(SignedQuery { signed: sign }): Query<SignedQuery>,
+                  headers: HeaderMap,
+                  m: InstanceManagerExt,
+) -> Resp {
+	let Some(instance) = m.read().await.find(&name) else {
+		return not_found();
+	};
+	let (manifest, history) = {
+		let instance = instance.read().await;
+		(Manifest::build(&instance).await?, ManifestHistory::new(&instance)?)
+	};
+	history.record(&manifest).await?;
+	let etag = manifest.etag();
+	if etag_matches(&headers, &etag) {
+		return with_etag(StatusCode::NOT_MODIFIED.into_response(), &etag);
+	}
+	with_etag(signed(manifest, sign).await?, &etag)
+}
+
+fn with_etag(mut res: Response<BoxBody>, etag: &str) -> Resp {
+	if let Ok(value) = HeaderValue::from_str(etag) {
+		res.headers_mut().insert(ETAG, value);
+	}
+	res.headers_mut().insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+	Ok(res)
+}
+
+async fn dist_zip(path: Path<InstancePath>, m: InstanceManagerExt, req: Request<Body>) -> Resp {
+	serve_zip(path, m, req, ZipKind::Dist).await
+}
+
+async fn config_zip(path: Path<InstancePath>, m: InstanceManagerExt, req: Request<Body>) -> Resp {
+	serve_zip(path, m, req, ZipKind::Config).await
+}
+
+async fn serve_zip(Path(InstancePath { name }): Path<InstancePath>, m: InstanceManagerExt, req: Request<Body>, kind: ZipKind) -> Resp {
+	let Some(instance) = m.read().await.find(&name) else {
+		return not_found();
+	};
+	let zip = zip_cache::get(&*instance.read().await, kind).await?;
+	let mut res = serve_file(&zip.path, &zip.etag(), req).await?;
+	if let Ok(value) = HeaderValue::from_str(&format!("attachment; filename=\"{}\"", zip.file_name(&name, kind.name()))) {
+		res.headers_mut().insert(CONTENT_DISPOSITION, value);
+	}
+	Ok(res)
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+	/// Bundle mmcupdater client (`export.client_jar`) which points to this server
+	#[serde(default)]
+	client: bool,
+}
+
+/// Instance zip which can be imported to MultiMC or Prism
+async fn export_multimc(Path(InstancePath { name }): Path<InstancePath>,
+                        Query(ExportQuery { client }): Query<ExportQuery>,
+                        m: InstanceManagerExt,
+                        _: Authorization,
+                        req: Request<Body>,
+) -> Resp {
+	let Some(instance) = m.read().await.find(&name) else {
+		return not_found();
+	};
+	let client = if client {
+		let config = get_config().await;
+		if config.export.client_jar.is_empty() {
+			return Err(ErrorWrapper::custom(StatusCode::BAD_REQUEST, "export.client_jar is not configured"));
+		}
+		let server = if config.export.public_url.is_empty() {
+			let Some(host) = req.headers().get(HOST).and_then(|it| it.to_str().ok()) else {
+				return Err(ErrorWrapper::custom(StatusCode::BAD_REQUEST, "export.public_url is not configured"));
+			};
+			format!("{}://{host}", if config.http.secure { "https" } else { "http" })
+		} else {
+			config.export.public_url.trim_end_matches('/').to_string()
+		};
+		Some(UpdaterClient { jar: PathBuf::from(&config.export.client_jar), server })
+	} else {
+		None
+	};
+	let zip_name = if client.is_some() { "multimc_client" } else { "multimc" };
+	let zip = {
+		let instance = instance.read().await;
+		let Some(entries) = multimc::instance_entries(&instance, client).await? else {
+			return Err(ErrorWrapper::custom(StatusCode::BAD_REQUEST, "Only vanilla, forge and neoforge instance can be played from launcher"));
+		};
+		zip_cache::build(&instance, zip_name, entries).await?
+	};
+	let mut res = serve_file(&zip.path, &zip.etag(), req).await?;
+	if let Ok(value) = HeaderValue::from_str(&format!("attachment; filename=\"{}\"", zip.file_name(&name, zip_name))) {
+		res.headers_mut().insert(CONTENT_DISPOSITION, value);
+	}
+	Ok(res)
+}
+
+/// Single distributed file by its name in manifest, etag is its sha256
+async fn dist_file(Path(DistPath { name, path }): Path<DistPath>, m: InstanceManagerExt, req: Request<Body>) -> Resp {
+	let Some(instance) = m.read().await.find(&name) else {
+		return not_found();
+	};
+	let Some((file, path)) = instance.read().await.dist_file(&path).await? else {
+		return not_found();
+	};
+	serve_file(&path, &format!("\"{}\"", file.hash), req).await
+}
+
+/// Stream file with `Range` (can be conditional with `If-Range`), `If-None-Match` and `If-Modified-Since` support
+async fn serve_file(path: &std::path::Path, etag: &str, mut req: Request<Body>) -> Resp {
+	if etag_matches(req.headers(), etag) {
+		return with_etag(StatusCode::NOT_MODIFIED.into_response(), etag);
+	}
+	// file is changed since client started downloading, send it from the beginning
+	let stale_range = req.headers().get(IF_RANGE)
+		.is_some_and(|it| it.to_str().map_or(true, |it| it.trim_start_matches("W/") != etag));
+	if stale_range {
+		req.headers_mut().remove(RANGE);
+	}
+	let res = ServeFile::new(path).oneshot(req).await?.map(boxed);
+	with_etag(res, etag)
+}
+
+#[derive(Deserialize)]
+struct DiffQuery {
+	/// Revision client currently has
+	from: String,
+	#[serde(default)]
+	signed: bool,
+}
+
+/// Changed files since client's revision; full manifest if that revision is expired
+async fn manifest_diff(Path(InstancePath { name }): Path<InstancePath>,
+                       Query(DiffQuery { from, signed: sign }): Query<DiffQuery>,
+                       headers: HeaderMap,
+                       m: InstanceManagerExt,
+) -> Resp {
+	let Some(instance) = m.read().await.find(&name) else {
+		return not_found();
+	};
+	let (manifest, history) = {
+		let instance = instance.read().await;
+		(Manifest::build(&instance).await?, ManifestHistory::new(&instance)?)
+	};
+	history.record(&manifest).await?;
+	let etag = manifest.etag();
+	if etag_matches(&headers, &etag) {
+		return with_etag(StatusCode::NOT_MODIFIED.into_response(), &etag);
+	}
+	with_etag(signed(history.delta(manifest, &from).await, sign).await?, &etag)
+}
+
+#[derive(Deserialize)]
+struct JavaSelect {
+	/// Id of java runtime from `/java`
+	id: String,
+}
+
+async fn java(Path(InstancePath { name }): Path<InstancePath>,
+              m: InstanceManagerExt,
+              _: Authorization,
+              Json(JavaSelect { id }): Json<JavaSelect>,
+) -> Resp {
+	let Some(java) = JavaManager::get_by_id(&id).await else {
+		return not_found();
+	};
 	let manager = m.read().await;
-	match manager.find(&name) {
-		None => {
-			let instance = manager.new_instance(&name, &version, typ).await?;
-			let it = instance.read().await;
-			created(it.clone())
+	let Some(instance) = manager.find(&name) else {
+		return not_found();
+	};
+	let mut instance = instance.write().await;
+	if let Some(requirement) = instance.java_requirement().await {
+		if !requirement.supported.contains(&java.version()) {
+			return Err(ErrorWrapper::custom(StatusCode::BAD_REQUEST, "Java version is not supported by this minecraft version"));
 		}
-		Some(_) => {
-			conflict()
+	}
+	instance.use_java(&java).await?;
+	got(instance.clone())
+}
+
+async fn mods(Path(InstancePath { name }): Path<InstancePath>, m: InstanceManagerExt, _: Authorization) -> Resp {
+	let manager = m.read().await;
+	let Some(instance) = manager.find(&name) else {
+		return not_found();
+	};
+	let instance = instance.read().await;
+	got(instance.list_mods().await?)
+}
+
+async fn validate_mods(Path(InstancePath { name }): Path<InstancePath>, m: InstanceManagerExt, _: Authorization) -> Resp {
+	let manager = m.read().await;
+	let Some(instance) = manager.find(&name) else {
+		return not_found();
+	};
+	let instance = instance.read().await;
+	got(instance.validate_mods().await?)
+}
+
+#[derive(Deserialize)]
+struct UpdateQuery {
+	/// Check again instead of returning result of the last check
+	#[serde(default)]
+	refresh: bool,
+}
+
+async fn mod_updates(Path(InstancePath { name }): Path<InstancePath>,
+                     Query(UpdateQuery { refresh }): Query<UpdateQuery>,
+                     m: InstanceManagerExt,
+                     _: Authorization,
+) -> Resp {
+	let Some(instance) = m.read().await.find(&name) else {
+		return not_found();
+	};
+	match UpdateManager::cached(&name).await {
+		Some(updates) if !refresh => { got(updates) }
+		_ => { got(UpdateManager::check(&instance).await?) }
+	}
+}
+
+#[derive(Deserialize)]
+struct ApplyUpdates {
+	/// Installed file names to update, every mod is updated if it's empty
+	#[serde(default)]
+	files: Vec<String>,
+	/// Restart server after update if it's running
+	#[serde(default)]
+	restart: bool,
+}
+
+async fn apply_updates(Path(InstancePath { name }): Path<InstancePath>,
+                       m: InstanceManagerExt,
+                       _: Authorization,
+                       Json(ApplyUpdates { files, restart }): Json<ApplyUpdates>,
+) -> Resp {
+	let Some(instance) = m.read().await.find(&name) else {
+		return not_found();
+	};
+	let job_name = format!("update mods of {name}");
+	let job = JobManager::spawn(job_name, |job| async move {
+		let updated = UpdateManager::apply(&instance, &files, &*job).await?;
+		if restart && !updated.is_empty() {
+			instance.write().await.restart_if_running().await;
 		}
+		Ok(())
+	}).await;
+	let Some(job) = job else {
+		return conflict();
+	};
+	accepted(job.info())
+}
+
+async fn mod_history(Path(InstancePath { name }): Path<InstancePath>, m: InstanceManagerExt, _: Authorization) -> Resp {
+	let Some(instance) = m.read().await.find(&name) else {
+		return not_found();
+	};
+	let history = instance.read().await.mod_history().await?;
+	got(history.entries())
+}
+
+#[derive(Deserialize)]
+struct HistoryPath {
+	name: String,
+	id: u64,
+}
+
+async fn rollback_mod(Path(HistoryPath { name, id }): Path<HistoryPath>,
+                      Query(RestartQuery { restart }): Query<RestartQuery>,
+                      m: InstanceManagerExt,
+                      _: Authorization,
+) -> Resp {
+	let Some(instance) = m.read().await.find(&name) else {
+		return not_found();
+	};
+	let mut instance = instance.write().await;
+	let Some(restored) = instance.rollback_mod(id).await? else {
+		return not_found();
+	};
+	if restart {
+		instance.restart_if_running().await;
+	}
+	got(restored)
+}
+
+async fn upload_mods(Path(InstancePath { name }): Path<InstancePath>,
+                     Query(RestartQuery { restart }): Query<RestartQuery>,
+                     m: InstanceManagerExt,
+                     _: Authorization,
+                     multipart: Multipart,
+) -> Resp {
+	let manager = m.read().await;
+	let Some(instance) = manager.find(&name) else {
+		return not_found();
+	};
+	let uploaded = instance.read().await.update(multipart).await?;
+	if restart && !uploaded.is_empty() {
+		instance.write().await.restart_if_running().await;
+	}
+	got(uploaded)
+}
+
+async fn remove_mod(Path(ModPath { name, file }): Path<ModPath>,
+                    Query(RestartQuery { restart }): Query<RestartQuery>,
+                    m: InstanceManagerExt,
+                    _: Authorization,
+) -> Resp {
+	let manager = m.read().await;
+	let Some(instance) = manager.find(&name) else {
+		return not_found();
+	};
+	let mut instance = instance.write().await;
+	if !instance.remove_mod(&file).await? {
+		return not_found();
+	}
+	if restart {
+		instance.restart_if_running().await;
 	}
-}
\ No newline at end of file
+	no_content()
+}
+
+#[derive(Deserialize)]
+struct ModToggle {
+	enabled: bool,
+}
+
+async fn toggle_mod(Path(ModPath { name, file }): Path<ModPath>,
+                    Query(RestartQuery { restart }): Query<RestartQuery>,
+                    m: InstanceManagerExt,
+                    _: Authorization,
+                    Json(ModToggle { enabled }): Json<ModToggle>,
+) -> Resp {
+	let manager = m.read().await;
+	let Some(instance) = manager.find(&name) else {
+		return not_found();
+	};
+	let Some(installed) = instance.read().await.set_mod_enabled(&file, enabled).await? else {
+		return not_found();
+	};
+	if restart {
+		instance.write().await.restart_if_running().await;
+	}
+	got(installed)
+}
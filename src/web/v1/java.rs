@@ -0,0 +1,61 @@
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::Router;
+use axum::routing::{get, post};
+use tracing::log::{debug, warn};
+
+use crate::manager::instance_manager::InstanceManagerExt;
+use crate::manager::job_manager::JobManager;
+use crate::util::errors::ErrorWrapper;
+use crate::util::errors::rest::{accepted, conflict, got, not_found, Resp};
+use crate::util::java::JavaManager;
+use crate::web::authentication::Authorization;
+
+pub fn build() -> Router {
+	debug!("Configuring java routes");
+	Router::new()
+		.route("/", get(all))
+		.route("/rescan", post(rescan))
+		// version to install; runtime id to remove
+		.route("/:java", post(install).delete(remove))
+}
+
+async fn all(_: Authorization) -> Resp {
+	got(JavaManager::versions().await)
+}
+
+async fn rescan(_: Authorization) -> Resp {
+	JavaManager::scan().await?;
+	got(JavaManager::versions().await)
+}
+
+async fn install(Path(version): Path<String>, _: Authorization) -> Resp {
+	let Ok(version) = version.parse::<u8>() else {
+		return Err(ErrorWrapper::custom(StatusCode::BAD_REQUEST, "Invalid java version"));
+	};
+	let name = format!("install java {version}");
+	let job = JobManager::spawn(name, |job| async move {
+		JavaManager::download_version_with(version, &*job).await?;
+		Ok(())
+	}).await;
+	let Some(job) = job else {
+		return conflict();
+	};
+	accepted(job.info())
+}
+
+async fn remove(Path(id): Path<String>, m: InstanceManagerExt, _: Authorization) -> Resp {
+	let Some(java) = JavaManager::get_by_id(&id).await else {
+		return not_found();
+	};
+	if java.runtime_dir().is_none() {
+		return Err(ErrorWrapper::custom(StatusCode::FORBIDDEN, "Java runtime is not managed by crate"));
+	}
+	let users = m.read().await.java_users(&java).await?;
+	if !users.is_empty() {
+		warn!("java {id} is used by {users:?}");
+		return conflict();
+	}
+	JavaManager::remove(&id).await?;
+	got(java)
+}
@@ -0,0 +1,74 @@
+use axum::extract::{Path, WebSocketUpgrade};
+use axum::extract::ws::{Message, WebSocket};
+use axum::response::Response;
+use axum::Router;
+use axum::routing::{get, post};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::log::{debug, trace};
+
+use crate::manager::job_manager::{JobId, JobManager};
+use crate::util::errors::rest::{conflict, got, no_content, not_found, Resp};
+use crate::web::authentication::Authorization;
+
+pub fn build() -> Router {
+	debug!("Configuring job routes");
+	Router::new()
+		.route("/", get(all))
+		.route("/ws", get(subscribe))
+		.route("/:id", get(info))
+		.route("/:id/cancel", post(cancel))
+}
+
+async fn all(_: Authorization) -> Resp {
+	got(JobManager::list().await)
+}
+
+async fn info(Path(id): Path<JobId>, _: Authorization) -> Resp {
+	match JobManager::get(id).await {
+		Some(job) => {
+			got(job)
+		}
+		None => {
+			not_found()
+		}
+	}
+}
+
+async fn cancel(Path(id): Path<JobId>, _: Authorization) -> Resp {
+	match JobManager::cancel(id).await {
+		Some(true) => {
+			no_content()
+		}
+		Some(false) => {
+			conflict()
+		}
+		None => {
+			not_found()
+		}
+	}
+}
+
+/// Push every job change as json text message
+async fn subscribe(ws: WebSocketUpgrade, _: Authorization) -> Response {
+	ws.on_upgrade(forward_events)
+}
+
+async fn forward_events(mut ws: WebSocket) {
+	let mut events = JobManager::subscribe();
+	loop {
+		let job = match events.recv().await {
+			Ok(job) => { job }
+			Err(RecvError::Lagged(skipped)) => {
+				trace!("job subscriber skipped {skipped} events");
+				continue;
+			}
+			Err(RecvError::Closed) => { break; }
+		};
+		let Ok(text) = serde_json::to_string(&job) else {
+			continue;
+		};
+		if ws.send(Message::Text(text)).await.is_err() {
+			break;
+		}
+	}
+}
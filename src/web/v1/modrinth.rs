@@ -0,0 +1,117 @@
+use axum::extract::{Path, Query};
+use axum::{Json, Router};
+use axum::routing::{get, post};
+use hashbrown::HashSet;
+use serde::Deserialize;
+use tracing::log::debug;
+
+use crate::instance::mc_mod::ModSource;
+use crate::manager::instance_manager::InstanceManagerExt;
+use crate::manager::job_manager::JobManager;
+use crate::mc::modrinth;
+use crate::mc::modrinth::ModrinthTarget;
+use crate::util::errors::rest::{accepted, conflict, got, not_found, Resp};
+use crate::util::http::{DownloadProgress, new_client};
+use crate::web::authentication::Authorization;
+
+/// Nested in `/instance/:name/modrinth`, every request is filtered by loader and version of the instance
+pub fn build() -> Router {
+	debug!("Configuring modrinth routes");
+	Router::new()
+		.route("/search", get(search))
+		.route("/project/:project", get(versions))
+		.route("/install", post(install))
+}
+
+#[derive(Deserialize)]
+struct InstancePath {
+	name: String,
+}
+
+#[derive(Deserialize)]
+struct ProjectPath {
+	name: String,
+	/// Project id or slug
+	project: String,
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+	#[serde(default)]
+	query: String,
+	#[serde(default)]
+	offset: u32,
+	#[serde(default = "default_limit")]
+	limit: u32,
+}
+
+const fn default_limit() -> u32 { 20 }
+
+async fn search(Path(InstancePath { name }): Path<InstancePath>,
+                Query(SearchQuery { query, offset, limit }): Query<SearchQuery>,
+                m: InstanceManagerExt,
+                _: Authorization,
+) -> Resp {
+	let Some(instance) = m.read().await.find(&name) else {
+		return not_found();
+	};
+	let target = {
+		let instance = instance.read().await;
+		ModrinthTarget::new(&instance.mod_type, instance.version())
+	};
+	got(modrinth::search(&new_client()?, &query, &target, offset, limit.min(100)).await?)
+}
+
+async fn versions(Path(ProjectPath { name, project }): Path<ProjectPath>, m: InstanceManagerExt, _: Authorization) -> Resp {
+	let Some(instance) = m.read().await.find(&name) else {
+		return not_found();
+	};
+	let target = {
+		let instance = instance.read().await;
+		ModrinthTarget::new(&instance.mod_type, instance.version())
+	};
+	got(modrinth::versions(&new_client()?, &project, &target).await?)
+}
+
+#[derive(Deserialize)]
+struct InstallRequest {
+	/// Project id or slug
+	project: String,
+	/// Latest compatible version if it's not specified
+	#[serde(default)]
+	version: Option<String>,
+}
+
+/// Install project with its required dependencies in background, respond with job
+async fn install(Path(InstancePath { name }): Path<InstancePath>,
+                 m: InstanceManagerExt,
+                 _: Authorization,
+                 Json(InstallRequest { project, version }): Json<InstallRequest>,
+) -> Resp {
+	let Some(instance) = m.read().await.find(&name) else {
+		return not_found();
+	};
+	let job_name = format!("install {project} to {name}");
+	let job = JobManager::spawn(job_name, |job| async move {
+		let (target, folder, installed) = {
+			let instance = instance.read().await;
+			let installed: HashSet<String> = instance.mods.iter()
+				.filter_map(|it| match &it.source {
+					Some(ModSource::Modrinth { project_id, .. }) => { Some(project_id.clone()) }
+					_ => { None }
+				})
+				.collect();
+			(ModrinthTarget::new(&instance.mod_type, instance.version()), instance.mod_dir()?, installed)
+		};
+		let client = new_client()?;
+		job.set_phase(&format!("resolving {project}"));
+		let versions = modrinth::resolve(&client, &project, version.as_deref(), &target, &installed).await?;
+		let mods = modrinth::install(&client, &versions, &folder, &*job).await?;
+		instance.write().await.record_mods(mods).await?;
+		Ok(())
+	}).await;
+	let Some(job) = job else {
+		return conflict();
+	};
+	accepted(job.info())
+}
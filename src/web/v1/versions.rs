@@ -0,0 +1,39 @@
+use axum::extract::{Path, Query};
+use axum::Router;
+use axum::routing::get;
+use serde::Deserialize;
+use tracing::log::debug;
+
+use crate::instance::mc_instance::ModType;
+use crate::mc::version_cache;
+use crate::util::errors::rest::{got, not_found, Resp};
+use crate::web::authentication::Authorization;
+
+pub fn build() -> Router {
+	debug!("Configuring version routes");
+	Router::new()
+		.route("/:loader", get(versions))
+}
+
+#[derive(Deserialize)]
+struct VersionQuery {
+	/// Ignore cached version list
+	#[serde(default)]
+	refresh: bool,
+}
+
+/// Versions of loader, newest first
+async fn versions(Path(loader): Path<String>, Query(VersionQuery { refresh }): Query<VersionQuery>, _: Authorization) -> Resp {
+	let versions = match ModType::from_loader_name(&loader) {
+		Some(mod_type) if refresh => {
+			version_cache::refresh(&mod_type).await?
+		}
+		Some(mod_type) => {
+			version_cache::versions(&mod_type).await?
+		}
+		None => {
+			return not_found();
+		}
+	};
+	got(versions.sorted())
+}